@@ -9,7 +9,7 @@ fn main() {
     test_me().unwrap()
 }
 
-fn test_me() -> std::result::Result<(), DiagnosticRecord> {
+fn test_me() -> Result<()> {
     let env = create_environment_v3().expect("Can't create ODBC environment");
     let conn = env.connect("PostgreSQL", "postgres", "postgres")?;
     let stmt = Statement::with_parent(&conn)?.prepare(
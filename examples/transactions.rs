@@ -16,7 +16,7 @@ fn main() {
     }
 }
 
-fn connect() -> std::result::Result<(), DiagnosticRecord> {
+fn connect() -> Result<()> {
     let env = create_environment_v3().map_err(|e| e.unwrap())?;
     let conn = env.connect("TestDataSource", "", "").unwrap();
     let mut conn = conn.disable_autocommit().unwrap();
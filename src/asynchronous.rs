@@ -0,0 +1,426 @@
+//! Asynchronous API running the blocking ODBC calls on tokio's blocking thread pool
+//!
+//! ODBC calls block the calling thread until the driver returns. To avoid stalling the executor
+//! every call is moved to a thread of the blocking pool using `tokio::task::spawn_blocking`. The
+//! futures returned by this module may be created anywhere, but spawn their task only once they
+//! are polled for the first time, which needs to happen from within a tokio runtime.
+//!
+//! Dropping a future before it completed cancels the statement it is executing using
+//! `SQLCancel`. Queries which did not start yet are not executed at all.
+use {ffi, Connection, Cursor, DiagnosticRecord, Environment, Error, Executed, Handle, HasResult, NoResult,
+     Allocated, Result, Statement, Version3};
+use futures_core::Stream;
+use odbc_safe::AutocommitOn;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic::resume_unwind;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+use tokio::task::{spawn_blocking, JoinHandle};
+
+type ResultSet = Statement<'static, 'static, Executed, HasResult, AutocommitOn>;
+type AllocatedStatement = Statement<'static, 'static, Allocated, NoResult, AutocommitOn>;
+
+/// A connection which executes statements on tokio's blocking thread pool
+///
+/// # Example
+///
+/// ```
+/// # extern crate odbc;
+/// # extern crate tokio;
+/// # use odbc::*;
+/// # use std::sync::Arc;
+/// # fn main() -> Result<()> {
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// let env = Arc::new(create_environment_v3().map_err(|e| e.unwrap())?);
+/// let conn = runtime.block_on(AsyncConnection::connect(&env, "DSN=TestDataSource;"))?;
+/// let stmt = runtime.block_on(conn.execute("SELECT TITLE FROM MOVIES"))?;
+/// let titles = runtime.block_on(stmt.fetch_batch(100, |cursor| cursor.get_data::<String>(1)))?;
+/// println!("{:?}", titles);
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncConnection {
+    connection: Arc<Connection<'static, AutocommitOn>>,
+}
+
+/// The result of a statement executed by an `AsyncConnection`
+pub struct AsyncStatement {
+    result_set: Option<SharedResultSet>,
+    affected_row_count: ffi::SQLLEN,
+}
+
+/// Stream of rows fetched in batches from an `AsyncStatement`
+///
+/// Created by `AsyncStatement::rows`.
+pub struct RowStream<T, F> {
+    result_set: Option<SharedResultSet>,
+    batch_size: usize,
+    row: Option<F>,
+    buffer: VecDeque<T>,
+    pending: Option<Blocking<(F, Vec<T>)>>,
+}
+
+impl AsyncConnection {
+    /// Connects to a data source described by `connection_str` without blocking the executor
+    pub fn connect(
+        env: &Arc<Environment<Version3>>,
+        connection_str: &str,
+    ) -> impl Future<Output = Result<AsyncConnection>> {
+        let env = env.clone();
+        let connection_str = connection_str.to_owned();
+        Blocking::spawn(None, move || {
+            Environment::connect_with_connection_string_shared(&env, &connection_str)
+                .map(AsyncConnection::from)
+        })
+    }
+
+    /// Returns the underlying connection, e.g. to use it with the blocking API
+    pub fn connection(&self) -> &Arc<Connection<'static, AutocommitOn>> {
+        &self.connection
+    }
+
+    /// Executes `statement_text` on a thread of the blocking pool
+    ///
+    /// Dropping the returned future before it completed cancels the execution.
+    pub fn execute(&self, statement_text: &str) -> impl Future<Output = Result<AsyncStatement>> {
+        let connection = self.connection.clone();
+        let statement_text = statement_text.to_owned();
+        // Holds the statement once it has been allocated, so it outlives any call to `SQLCancel`
+        let stmt: Arc<Mutex<Option<AllocatedStatement>>> = Arc::new(Mutex::new(None));
+        let canceller = Canceller::new(stmt.clone());
+        let state = canceller.state.clone();
+        Blocking::spawn(Some(canceller), move || {
+            let mut guard = lock(&stmt);
+            *guard = Some(Statement::with_shared_parent(&connection)?);
+            let handle = unsafe { guard.as_ref().expect("statement has just been allocated").handle() };
+            start(&state, handle)?;
+            let has_result = {
+                let allocated = guard.as_mut().expect("statement is only taken once");
+                allocated.exec_direct_in_place(&statement_text)
+            };
+            // From here on the statement must no longer be cancelled, as it may be freed
+            *lock(&state) = TaskState::Finished;
+            let has_result = has_result?;
+            let stmt = guard.take().expect("statement is only taken once");
+            let affected_row_count = stmt.affected_row_count()?;
+            Ok(AsyncStatement {
+                result_set: if has_result {
+                    Some(SharedResultSet {
+                        result_set: Arc::new(Mutex::new(stmt.into_result_set())),
+                        handle: handle as usize,
+                    })
+                } else {
+                    None
+                },
+                affected_row_count,
+            })
+        })
+    }
+}
+
+impl From<Connection<'static, AutocommitOn>> for AsyncConnection {
+    fn from(connection: Connection<'static, AutocommitOn>) -> Self {
+        AsyncConnection { connection: Arc::new(connection) }
+    }
+}
+
+impl From<Arc<Connection<'static, AutocommitOn>>> for AsyncConnection {
+    fn from(connection: Arc<Connection<'static, AutocommitOn>>) -> Self {
+        AsyncConnection { connection }
+    }
+}
+
+impl AsyncStatement {
+    /// `true` if the statement created a result set
+    pub fn has_result(&self) -> bool {
+        self.result_set.is_some()
+    }
+
+    /// The number of rows affected by an `UPDATE`, `INSERT` or `DELETE` statement
+    ///
+    /// Like `Statement::affected_row_count` this may be `-1` if the number is not available.
+    pub fn affected_row_count(&self) -> ffi::SQLLEN {
+        self.affected_row_count
+    }
+
+    /// Fetches up to `max_rows` rows on a thread of the blocking pool
+    ///
+    /// `row` is invoked once for every fetched row and converts it into a `T`. Returns fewer than
+    /// `max_rows` rows only if the result set is exhausted. Dropping the returned future before
+    /// it completed cancels the statement.
+    pub fn fetch_batch<T, F>(
+        &self,
+        max_rows: usize,
+        mut row: F,
+    ) -> impl Future<Output = Result<Vec<T>>>
+    where
+        T: Send + 'static,
+        F: FnMut(&mut Cursor<Executed, AutocommitOn>) -> Result<T> + Send + 'static,
+    {
+        match self.result_set {
+            Some(ref result_set) => result_set.fetch(max_rows, move |batch| {
+                batch.fetch(&mut row)
+            }),
+            None => Blocking::ready(Vec::new()),
+        }
+    }
+
+    /// Turns the result set into a stream of rows, fetching `batch_size` rows at once
+    pub fn rows<T, F>(self, batch_size: usize, row: F) -> RowStream<T, F>
+    where
+        T: Send + 'static,
+        F: FnMut(&mut Cursor<Executed, AutocommitOn>) -> Result<T> + Send + 'static,
+    {
+        RowStream {
+            result_set: self.result_set,
+            batch_size: batch_size.max(1),
+            row: Some(row),
+            buffer: VecDeque::new(),
+            pending: None,
+        }
+    }
+}
+
+// Nothing is ever pinned in place, the closure is moved into each blocking task anyway
+impl<T, F> Unpin for RowStream<T, F> {}
+
+impl<T, F> Stream for RowStream<T, F>
+where
+    T: Send + 'static,
+    F: FnMut(&mut Cursor<Executed, AutocommitOn>) -> Result<T> + Send + 'static,
+{
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<T>>> {
+        loop {
+            if let Some(row) = self.buffer.pop_front() {
+                return Poll::Ready(Some(Ok(row)));
+            }
+            if self.pending.is_none() {
+                // The closure is only missing once the result set is exhausted or failed
+                let mut row = match self.row.take() {
+                    Some(row) => row,
+                    None => return Poll::Ready(None),
+                };
+                let pending = match self.result_set {
+                    Some(ref result_set) => result_set.fetch(self.batch_size, move |batch| {
+                        let rows = batch.fetch(&mut row)?;
+                        Ok((row, rows))
+                    }),
+                    None => return Poll::Ready(None),
+                };
+                self.pending = Some(pending);
+            }
+            let batch = match Pin::new(self.pending.as_mut().unwrap()).poll(cx) {
+                Poll::Ready(batch) => batch,
+                Poll::Pending => return Poll::Pending,
+            };
+            self.pending = None;
+            match batch {
+                Ok((row, rows)) => {
+                    if rows.len() == self.batch_size {
+                        self.row = Some(row);
+                    }
+                    self.buffer.extend(rows);
+                }
+                Err(error) => return Poll::Ready(Some(Err(error))),
+            }
+        }
+    }
+}
+
+/// Rows fetched from a result set on a thread of the blocking pool
+struct Batch<'r> {
+    result_set: MutexGuard<'r, ResultSet>,
+    max_rows: usize,
+}
+
+impl<'r> Batch<'r> {
+    fn fetch<T, F>(&mut self, row: &mut F) -> Result<Vec<T>>
+    where
+        F: FnMut(&mut Cursor<Executed, AutocommitOn>) -> Result<T>,
+    {
+        let mut rows = Vec::new();
+        while rows.len() < self.max_rows {
+            match self.result_set.fetch()? {
+                Some(mut cursor) => rows.push(row(&mut cursor)?),
+                None => break,
+            }
+        }
+        Ok(rows)
+    }
+}
+
+/// Result set shared between an `AsyncStatement` and the tasks fetching from it
+struct SharedResultSet {
+    result_set: Arc<Mutex<ResultSet>>,
+    // Raw statement handle, so it can be cancelled without waiting for the lock
+    handle: usize,
+}
+
+impl SharedResultSet {
+    /// Runs `f` with the locked result set on a thread of the blocking pool
+    fn fetch<R, F>(&self, max_rows: usize, f: F) -> Blocking<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut Batch) -> Result<R> + Send + 'static,
+    {
+        let result_set = self.result_set.clone();
+        let handle = self.handle;
+        let canceller = Canceller::new(result_set.clone());
+        let state = canceller.state.clone();
+        Blocking::spawn(Some(canceller), move || {
+            let mut batch = Batch { result_set: lock(&result_set), max_rows };
+            start(&state, handle as ffi::SQLHSTMT)?;
+            let result = f(&mut batch);
+            *lock(&state) = TaskState::Finished;
+            result
+        })
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // A panic in a user supplied closure does not leave the statement in an invalid state
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Diagnostics reported for operations which have been cancelled before they could complete
+fn cancelled() -> Error {
+    DiagnosticRecord::with_message(b"HY008", "Operation canceled").into()
+}
+
+/// Marks the task as executing ODBC calls on `handle`, unless it has been cancelled already
+fn start(state: &Mutex<TaskState>, handle: ffi::SQLHSTMT) -> Result<()> {
+    let mut state = lock(state);
+    if *state == TaskState::Cancelled {
+        return Err(cancelled());
+    }
+    *state = TaskState::Running(handle as usize);
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TaskState {
+    /// Task has not been spawned yet, or did not start executing ODBC calls
+    Pending,
+    /// Task is executing ODBC calls on the statement handle
+    Running(usize),
+    /// Task is done using the statement handle
+    Finished,
+    /// Future has been dropped before the task started
+    Cancelled,
+}
+
+/// Cancels the statement a task is executing, if the future waiting for it is dropped early
+struct Canceller {
+    state: Arc<Mutex<TaskState>>,
+    // Keeps the statement handle alive as long as `SQLCancel` may be called on it
+    _statement: Arc<dyn Send + Sync>,
+}
+
+impl Canceller {
+    fn new(statement: Arc<dyn Send + Sync>) -> Canceller {
+        Canceller {
+            state: Arc::new(Mutex::new(TaskState::Pending)),
+            _statement: statement,
+        }
+    }
+
+    fn cancel(&self) {
+        let mut state = lock(&self.state);
+        match *state {
+            TaskState::Pending => *state = TaskState::Cancelled,
+            TaskState::Running(handle) => unsafe {
+                // Holding the lock ensures the task did not yet free or reuse the handle. Nobody
+                // is waiting for the outcome, so there is no one to report a failure to either.
+                let _ = ffi::SQLCancel(handle as ffi::SQLHSTMT);
+            },
+            TaskState::Finished | TaskState::Cancelled => (),
+        }
+    }
+}
+
+/// Future resolving to the result of a closure running on a thread of the blocking pool
+struct Blocking<T> {
+    task: Task<T>,
+    // Only `None` for futures which can not be cancelled or are done
+    canceller: Option<Canceller>,
+}
+
+enum Task<T> {
+    /// Not polled yet. Spawning requires a runtime, which is only guaranteed once polled.
+    Idle(Option<Box<dyn FnOnce() -> Result<T> + Send>>),
+    Running(JoinHandle<Result<T>>),
+    // Boxed, as diagnostic records are large compared to a join handle
+    Ready(Option<Box<Result<T>>>),
+}
+
+impl<T: Send + 'static> Blocking<T> {
+    fn spawn<F>(canceller: Option<Canceller>, f: F) -> Blocking<T>
+    where
+        F: FnOnce() -> Result<T> + Send + 'static,
+    {
+        Blocking { task: Task::Idle(Some(Box::new(f))), canceller }
+    }
+
+    fn ready(value: T) -> Blocking<T> {
+        Blocking { task: Task::Ready(Some(Box::new(Ok(value)))), canceller: None }
+    }
+}
+
+// Neither the result nor the join handle are ever pinned in place
+impl<T> Unpin for Blocking<T> {}
+
+impl<T: Send + 'static> Future for Blocking<T> {
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<T>> {
+        let this = &mut *self;
+        loop {
+            let joined = match this.task {
+                Task::Idle(ref mut f) => {
+                    let f = f.take().expect("task is only spawned once");
+                    let state = this.canceller.as_ref().map(|canceller| canceller.state.clone());
+                    this.task = Task::Running(spawn_blocking(move || {
+                        // Dropped after being polled, but before the task started
+                        if let Some(state) = state {
+                            if *lock(&state) == TaskState::Cancelled {
+                                return Err(cancelled());
+                            }
+                        }
+                        f()
+                    }));
+                    continue;
+                }
+                Task::Running(ref mut task) => match Pin::new(task).poll(cx) {
+                    Poll::Ready(joined) => joined,
+                    Poll::Pending => return Poll::Pending,
+                },
+                Task::Ready(ref mut result) => {
+                    return Poll::Ready(*result.take().expect("future polled after completion"))
+                }
+            };
+            // The task is done, so there is nothing left to cancel
+            this.canceller = None;
+            this.task = Task::Ready(None);
+            return match joined {
+                Ok(result) => Poll::Ready(result),
+                Err(error) => match error.try_into_panic() {
+                    Ok(panic) => resume_unwind(panic),
+                    // Runtime is shutting down
+                    Err(_) => Poll::Ready(Err(cancelled())),
+                },
+            };
+        }
+    }
+}
+
+impl<T> Drop for Blocking<T> {
+    fn drop(&mut self) {
+        if let Some(ref canceller) = self.canceller {
+            canceller.cancel();
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Delays between polls of operations the driver executes asynchronously
+//!
+//! ODBC does not notify the application once an asynchronous operation completes, it has to call
+//! the function again to find out. Rather than asking the executor to poll again right away, which
+//! would keep a core busy for as long as the operation takes, pending futures are woken by a timer
+//! thread once a delay elapsed. The delay doubles from `MIN_DELAY` up to `MAX_DELAY`, so short
+//! operations complete with little latency and long ones are polled only a few times a second.
+//!
+//! The timer thread is spawned the first time a future needs to wait, and stays idle while none
+//! does. It does not depend on any particular executor.
+use std::cmp;
+use std::mem;
+use std::sync::{Condvar, Mutex, MutexGuard, Once};
+use std::task::Waker;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Delay before polling a pending operation for the first time
+const MIN_DELAY: Duration = Duration::from_millis(1);
+/// Upper bound of the delay between two polls of a pending operation
+const MAX_DELAY: Duration = Duration::from_millis(50);
+
+/// Wakers to wake and when to wake them
+static QUEUE: Mutex<Vec<(Instant, Waker)>> = Mutex::new(Vec::new());
+/// Notified whenever a waker is added to `QUEUE`
+static SCHEDULED: Condvar = Condvar::new();
+static START: Once = Once::new();
+
+/// Delays between polls of a single operation
+pub(crate) struct Backoff {
+    delay: Duration,
+}
+
+impl Backoff {
+    pub(crate) fn new() -> Backoff {
+        Backoff { delay: MIN_DELAY }
+    }
+
+    fn next_delay(&mut self) -> Duration {
+        let delay = self.delay;
+        self.delay = cmp::min(delay * 2, MAX_DELAY);
+        delay
+    }
+
+    /// Wakes `waker` once the next delay elapsed
+    pub(crate) fn wake_later(&mut self, waker: &Waker) {
+        let deadline = Instant::now() + self.next_delay();
+        START.call_once(|| {
+            thread::Builder::new()
+                .name("odbc-backoff".to_owned())
+                .spawn(run)
+                .expect("Failed to spawn the thread waking pending ODBC operations");
+        });
+        lock().push((deadline, waker.clone()));
+        SCHEDULED.notify_one();
+    }
+
+    /// Blocks the calling thread for the next delay. Used by `Drop` implementations, which can not
+    /// wait for the driver asynchronously.
+    pub(crate) fn sleep(&mut self) {
+        thread::sleep(self.next_delay());
+    }
+}
+
+fn lock() -> MutexGuard<'static, Vec<(Instant, Waker)>> {
+    // Entries are pushed and removed as a whole, so the queue is consistent even after a panic
+    QUEUE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Runs the timer thread
+fn run() {
+    let mut queue = lock();
+    loop {
+        let now = Instant::now();
+        let (due, pending) = mem::take(&mut *queue)
+            .into_iter()
+            .partition::<Vec<_>, _>(|&(deadline, _)| deadline <= now);
+        *queue = pending;
+        if !due.is_empty() {
+            // Waking a task may take a while, do not block futures scheduling a wake up meanwhile
+            drop(queue);
+            for (_, waker) in due {
+                waker.wake();
+            }
+            queue = lock();
+            continue;
+        }
+        queue = match queue.iter().map(|&(deadline, _)| deadline).min() {
+            Some(deadline) => SCHEDULED
+                .wait_timeout(queue, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0,
+            None => SCHEDULED.wait(queue).unwrap_or_else(|e| e.into_inner()),
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn delay_doubles_up_to_max() {
+        let mut backoff = Backoff::new();
+        assert_eq!(backoff.next_delay(), Duration::from_millis(1));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(2));
+        for _ in 0..10 {
+            backoff.next_delay();
+        }
+        assert_eq!(backoff.next_delay(), MAX_DELAY);
+    }
+}
@@ -1,12 +1,50 @@
 //! Holds implementation of odbc connection
-use super::{ffi, safe, Environment, Handle, Result, Version3};
-use super::result::{into_result, into_result_with};
+use super::{ffi, safe, DiagnosticRecord, Encodings, Environment, Handle, HasEncodings, Result, Return, SqlText, Version3};
+use super::backoff::Backoff;
+use super::diagnostics::Decoded;
+use super::result::into_result_with;
+use super::statement::StatementCache;
+use super::warnings::{collect_warnings, Collecting, HasWarnings, WarningHandler, Warnings};
+use encoding_rs::UTF_8;
 use odbc_safe::{AutocommitMode, AutocommitOn, AutocommitOff};
+use std::cmp;
+use std::future::Future;
+use std::mem::transmute;
+use std::pin::Pin;
+use std::ptr::null_mut;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+// Values of `SQL_ATTR_CONNECTION_DEAD` and `SQL_ATTR_AUTOCOMMIT` not exposed by odbc-sys
+const SQL_CD_TRUE: ffi::SQLUINTEGER = 1;
+const SQL_AUTOCOMMIT_ON: usize = 1;
 
 /// Represents a connection to an ODBC data source
 #[derive(Debug)]
 pub struct Connection<'env, AC: AutocommitMode> {
+    // Prepared statements kept for reuse by `prepare_cached`. Declared first, so the cached
+    // handles are freed before the connection is.
+    statement_cache: Mutex<StatementCache>,
     safe: safe::Connection<'env, AC>,
+    // Keeps a shared environment alive for connections which do not borrow it. Fields are dropped
+    // in declaration order, so the connection is always freed before the environment.
+    env: Option<Arc<Environment<Version3>>>,
+    encodings: Encodings,
+    // Warnings reported for the connection, until taken by the application. Boxed to keep the
+    // connection small, since `disable_autocommit` and `enable_autocommit` return it on failure.
+    warnings: Box<Warnings>,
+}
+
+impl<'env, AC: AutocommitMode> HasEncodings for Connection<'env, AC> {
+    fn encodings(&self) -> Encodings {
+        self.encodings
+    }
+}
+
+impl<'env, AC: AutocommitMode> HasWarnings for Connection<'env, AC> {
+    fn warnings(&self) -> Option<&Warnings> {
+        Some(&*self.warnings)
+    }
 }
 
 impl<'env, AC: AutocommitMode> Handle for Connection<'env, AC> {
@@ -25,9 +63,25 @@ impl Environment<Version3> {
     /// * `usr` - User identifier
     /// * `pwd` - Authentication (usually password)
     pub fn connect<'env>(&'env self, dsn: &str, usr: &str, pwd: &str) -> Result<Connection<'env, AutocommitOn>> {
-        let safe = into_result_with(self, safe::DataSource::with_parent(self.as_safe()))?;
-        let safe = into_result(safe.connect(dsn, usr, pwd))?;
-        Ok(Connection { safe })
+        let data_source = into_result_with(self, "SQLAllocHandle", safe::DataSource::with_parent(self.as_safe()))?;
+        let encodings = self.encodings();
+        let warnings = Box::new(Warnings::new(None));
+        let decoded = Decoded::new(data_source.as_raw(), encodings);
+        let diag = Collecting { diag: &decoded, warnings: &warnings };
+        let safe = if encodings.wide {
+            connect_wide(data_source.as_raw(), dsn, usr, pwd).into_result(&diag)?;
+            // The connection attempt succeeded, so the handle is connected now
+            unsafe { safe::DataSource::from_raw(data_source.into_raw()) }
+        } else {
+            into_result_with(&diag, "SQLConnect", data_source.connect(dsn, usr, pwd))?
+        };
+        Ok(Connection {
+            statement_cache: Mutex::new(StatementCache::new()),
+            safe,
+            env: None,
+            encodings,
+            warnings,
+        })
     }
 
     /// Connects to an ODBC data source using a connection string
@@ -38,19 +92,384 @@ impl Environment<Version3> {
         &'env self,
         connection_str: &str,
     ) -> Result<Connection<'env, AutocommitOn>> {
-        let safe = into_result_with(self, safe::DataSource::with_parent(self.as_safe()))?;
-        let safe = into_result(safe.connect_with_connection_string(connection_str))?;
-        Ok(Connection { safe })
+        let data_source = into_result_with(self, "SQLAllocHandle", safe::DataSource::with_parent(self.as_safe()))?;
+        let encodings = self.encodings();
+        let warnings = Box::new(Warnings::new(None));
+        let decoded = Decoded::new(data_source.as_raw(), encodings);
+        let diag = Collecting { diag: &decoded, warnings: &warnings };
+        let safe = if encodings.wide {
+            let text = SqlText::new(connection_str, UTF_8, true);
+            driver_connect(data_source.as_raw(), &text).into_result(&diag)?;
+            // The connection attempt succeeded, so the handle is connected now
+            unsafe { safe::DataSource::from_raw(data_source.into_raw()) }
+        } else {
+            into_result_with(&diag, "SQLDriverConnect", data_source.connect_with_connection_string(connection_str))?
+        };
+        Ok(Connection {
+            statement_cache: Mutex::new(StatementCache::new()),
+            safe,
+            env: None,
+            encodings,
+            warnings,
+        })
+    }
+
+    /// Connects to an ODBC data source, like `connect`. The returned connection holds a reference
+    /// to `env` instead of borrowing it, so it is not bound to any lifetime and can e.g. be stored
+    /// in a connection pool.
+    ///
+    /// # Example
+    /// ```
+    /// # use odbc::*;
+    /// # use std::sync::Arc;
+    /// # fn doc() -> Result<()> {
+    /// let env = Arc::new(create_environment_v3().map_err(|e| e.unwrap())?);
+    /// let conn: Connection<'static, safe::AutocommitOn> =
+    ///     Environment::connect_shared(&env, "TestDataSource", "", "")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn connect_shared(
+        env: &Arc<Self>,
+        dsn: &str,
+        usr: &str,
+        pwd: &str,
+    ) -> Result<Connection<'static, AutocommitOn>> {
+        let connection = env.connect(dsn, usr, pwd)?;
+        Ok(Connection::into_shared(connection, env))
+    }
+
+    /// Connects to an ODBC data source using a connection string, like
+    /// `connect_with_connection_string`. The returned connection holds a reference to `env`
+    /// instead of borrowing it, so it is not bound to any lifetime.
+    pub fn connect_with_connection_string_shared(
+        env: &Arc<Self>,
+        connection_str: &str,
+    ) -> Result<Connection<'static, AutocommitOn>> {
+        let connection = env.connect_with_connection_string(connection_str)?;
+        Ok(Connection::into_shared(connection, env))
+    }
+}
+
+impl Environment<Version3> {
+    /// Connects to an ODBC data source using a connection string, like
+    /// `connect_with_connection_string`, but without blocking if the driver supports asynchronous
+    /// connection functions (`SQL_ATTR_ASYNC_DBC_FUNCTIONS_ENABLE`). Other drivers connect
+    /// synchronously when the returned future is polled.
+    ///
+    /// # Example
+    /// ```
+    /// # extern crate odbc;
+    /// # extern crate futures_executor;
+    /// # use odbc::*;
+    /// # fn main() -> Result<()> {
+    /// let env = create_environment_v3().map_err(|e| e.unwrap())?;
+    /// let connect = env.connect_with_connection_string_async("DSN=TestDataSource;");
+    /// let conn = futures_executor::block_on(connect)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn connect_with_connection_string_async<'env>(
+        &'env self,
+        connection_str: &str,
+    ) -> ConnectAsync<'env> {
+        if connection_str.len() > ffi::SQLSMALLINT::MAX as usize {
+            panic!("Connection string too long");
+        }
+        ConnectAsync {
+            env: self,
+            connection_str: SqlText::new(connection_str, UTF_8, self.encodings().wide),
+            data_source: None,
+            async_enabled: false,
+            backoff: Backoff::new(),
+        }
+    }
+}
+
+/// Future returned by `Environment::connect_with_connection_string_async`
+///
+/// Dropping it before completion cancels the connection attempt. The dropping thread is blocked
+/// until the driver acknowledged the cancellation.
+pub struct ConnectAsync<'env> {
+    env: &'env Environment<Version3>,
+    connection_str: SqlText,
+    // Allocated on first poll. Stays `Some` while the driver is still connecting.
+    data_source: Option<safe::DataSource<'env, safe::Unconnected<'env>>>,
+    async_enabled: bool,
+    backoff: Backoff,
+}
+
+impl<'env> ConnectAsync<'env> {
+    fn poll_driver_connect(&self) -> Poll<Return<()>> {
+        let data_source = self.data_source.as_ref().expect("future polled after completion");
+        match call_driver_connect(data_source.as_raw(), &self.connection_str) {
+            ffi::SQL_STILL_EXECUTING => Poll::Pending,
+            ret => Poll::Ready(driver_connect_result(ret)),
+        }
+    }
+}
+
+/// Calls `SQLDriverConnect`, or `SQLDriverConnectW` for wide text, without prompting
+fn call_driver_connect(hdbc: ffi::SQLHDBC, connection_str: &SqlText) -> ffi::SQLRETURN {
+    let length = connection_str.len() as ffi::SQLSMALLINT;
+    unsafe {
+        match *connection_str {
+            SqlText::Narrow(ref bytes) => ffi::SQLDriverConnect(
+                hdbc,
+                null_mut(),
+                bytes.as_ptr(),
+                length,
+                null_mut(),
+                0,
+                null_mut(),
+                ffi::SQL_DRIVER_NOPROMPT,
+            ),
+            SqlText::Wide(ref units) => ffi::SQLDriverConnectW(
+                hdbc,
+                null_mut(),
+                units.as_ptr(),
+                length,
+                null_mut(),
+                0,
+                null_mut(),
+                ffi::SQL_DRIVER_NOPROMPT,
+            ),
+        }
+    }
+}
+
+fn driver_connect_result(ret: ffi::SQLRETURN) -> Return<()> {
+    match ret {
+        ffi::SQL_SUCCESS => Return::Success(()),
+        ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(()),
+        ffi::SQL_ERROR => Return::Error("SQLDriverConnect"),
+        r => panic!("SQLDriverConnect returned unexpected result: {:?}", r),
+    }
+}
+
+/// Connects synchronously using `SQLDriverConnect` or `SQLDriverConnectW`
+fn driver_connect(hdbc: ffi::SQLHDBC, connection_str: &SqlText) -> Return<()> {
+    if connection_str.len() > ffi::SQLSMALLINT::MAX as usize {
+        panic!("Connection string too long");
+    }
+    driver_connect_result(call_driver_connect(hdbc, connection_str))
+}
+
+/// Connects to a data source using `SQLConnectW`
+fn connect_wide(hdbc: ffi::SQLHDBC, dsn: &str, usr: &str, pwd: &str) -> Return<()> {
+    let dsn: Vec<u16> = dsn.encode_utf16().collect();
+    let usr: Vec<u16> = usr.encode_utf16().collect();
+    let pwd: Vec<u16> = pwd.encode_utf16().collect();
+    match unsafe {
+        ffi::SQLConnectW(
+            hdbc,
+            dsn.as_ptr(),
+            dsn.len() as ffi::SQLSMALLINT,
+            usr.as_ptr(),
+            usr.len() as ffi::SQLSMALLINT,
+            pwd.as_ptr(),
+            pwd.len() as ffi::SQLSMALLINT,
+        )
+    } {
+        ffi::SQL_SUCCESS => Return::Success(()),
+        ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(()),
+        ffi::SQL_ERROR => Return::Error("SQLConnectW"),
+        r => panic!("SQLConnectW returned unexpected result: {:?}", r),
+    }
+}
+
+/// Translates `sql_text` using `SQLNativeSql`, or `SQLNativeSqlW` for wide text
+fn native_sql(hdbc: ffi::SQLHDBC, sql_text: &str, encodings: Encodings) -> Return<String> {
+    let text = SqlText::new(sql_text, encodings.db, encodings.wide);
+    // Capacity in code units, excluding the terminating zero. Translating escape sequences rarely
+    // grows the text by much.
+    let mut capacity = text.len() * 2 + 64;
+    loop {
+        let mut length: ffi::SQLINTEGER = 0;
+        let (ret, native) = unsafe {
+            match text {
+                SqlText::Narrow(ref bytes) => {
+                    let mut buffer = vec![0u8; capacity + 1];
+                    let ret = ffi::SQLNativeSql(
+                        hdbc,
+                        bytes.as_ptr(),
+                        bytes.len() as ffi::SQLINTEGER,
+                        buffer.as_mut_ptr(),
+                        buffer.len() as ffi::SQLINTEGER,
+                        &mut length as *mut ffi::SQLINTEGER,
+                    );
+                    let end = cmp::min(cmp::max(length, 0) as usize, capacity);
+                    (ret, encodings.db.decode(&buffer[..end]).0.into_owned())
+                }
+                SqlText::Wide(ref units) => {
+                    let mut buffer = vec![0u16; capacity + 1];
+                    let ret = ffi::SQLNativeSqlW(
+                        hdbc,
+                        units.as_ptr(),
+                        units.len() as ffi::SQLINTEGER,
+                        buffer.as_mut_ptr(),
+                        buffer.len() as ffi::SQLINTEGER,
+                        &mut length as *mut ffi::SQLINTEGER,
+                    );
+                    let end = cmp::min(cmp::max(length, 0) as usize, capacity);
+                    (ret, String::from_utf16_lossy(&buffer[..end]))
+                }
+            }
+        };
+        match ret {
+            ffi::SQL_SUCCESS => return Return::Success(native),
+            ffi::SQL_SUCCESS_WITH_INFO if length as usize > capacity && length > 0 => {
+                // Truncated, retry with a buffer large enough for the whole text
+                capacity = length as usize;
+            }
+            ffi::SQL_SUCCESS_WITH_INFO => return Return::SuccessWithInfo(native),
+            ffi::SQL_ERROR => return Return::Error("SQLNativeSql"),
+            r => panic!("SQLNativeSql returned unexpected result: {:?}", r),
+        }
+    }
+}
+
+/// Sets `SQL_ATTR_ASYNC_DBC_FUNCTIONS_ENABLE`
+fn set_async_dbc_functions(hdbc: ffi::SQLHDBC, enable: bool) -> Return<()> {
+    let value = if enable {
+        ffi::SQL_ASYNC_DBC_ENABLE_ON
+    } else {
+        ffi::SQL_ASYNC_DBC_ENABLE_OFF
+    };
+    match unsafe {
+        ffi::SQLSetConnectAttr(
+            hdbc,
+            ffi::SQL_ATTR_ASYNC_DBC_FUNCTIONS_ENABLE,
+            value as usize as ffi::SQLPOINTER,
+            ffi::SQL_IS_INTEGER as ffi::SQLINTEGER,
+        )
+    } {
+        ffi::SQL_SUCCESS => Return::Success(()),
+        ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(()),
+        ffi::SQL_ERROR => Return::Error("SQLSetConnectAttr"),
+        r => panic!("SQLSetConnectAttr returned unexpected result: {:?}", r),
+    }
+}
+
+// Nothing is ever pinned in place
+impl<'env> Unpin for ConnectAsync<'env> {}
+
+impl<'env> Future for ConnectAsync<'env> {
+    type Output = Result<Connection<'env, AutocommitOn>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if this.data_source.is_none() {
+            let data_source =
+                into_result_with(this.env, "SQLAllocHandle", safe::DataSource::with_parent(this.env.as_safe()))?;
+            this.async_enabled = match set_async_dbc_functions(data_source.as_raw(), true) {
+                Return::Success(()) => true,
+                Return::SuccessWithInfo(()) | Return::Error(_) => {
+                    debug!("Driver does not support asynchronous connection functions");
+                    false
+                }
+            };
+            this.data_source = Some(data_source);
+        }
+        let ret = match this.poll_driver_connect() {
+            Poll::Ready(ret) => ret,
+            Poll::Pending => {
+                this.backoff.wake_later(cx.waker());
+                return Poll::Pending;
+            }
+        };
+        let data_source = this.data_source.take().unwrap();
+        let encodings = this.env.encodings();
+        let warnings = Box::new(Warnings::new(None));
+        ret.into_result(&Collecting { diag: &Decoded::new(data_source.as_raw(), encodings), warnings: &warnings })?;
+        // The connection attempt succeeded, so the handle is connected now
+        let safe: safe::Connection<'env, AutocommitOn> =
+            unsafe { safe::DataSource::from_raw(data_source.into_raw()) };
+        let connection = Connection {
+            statement_cache: Mutex::new(StatementCache::new()),
+            safe,
+            env: None,
+            encodings,
+            warnings,
+        };
+        if this.async_enabled {
+            // Leave the connection usable with the blocking API
+            set_async_dbc_functions(connection.safe.as_raw(), false).into_result(&connection)?;
+        }
+        Poll::Ready(Ok(connection))
+    }
+}
+
+impl<'env> Drop for ConnectAsync<'env> {
+    fn drop(&mut self) {
+        let ret = match self.data_source {
+            Some(ref data_source) => unsafe {
+                let _ = ffi::SQLCancelHandle(ffi::SQL_HANDLE_DBC, data_source.as_raw() as ffi::SQLHANDLE);
+                // The handle borrows the environment, so it can not be handed to another thread
+                // to wait for the driver. Block, but do not spin.
+                let mut backoff = Backoff::new();
+                loop {
+                    if let Poll::Ready(ret) = self.poll_driver_connect() {
+                        break ret;
+                    }
+                    backoff.sleep();
+                }
+            },
+            None => return,
+        };
+        let data_source = self.data_source.take().unwrap();
+        match ret {
+            Return::Success(()) | Return::SuccessWithInfo(()) => {
+                // Connected after all, disconnect again
+                let _ = set_async_dbc_functions(data_source.as_raw(), false);
+                let _connection: safe::Connection<'env, AutocommitOn> =
+                    unsafe { safe::DataSource::from_raw(data_source.into_raw()) };
+            }
+            Return::Error(_) => (),
+        }
+    }
+}
+
+impl Connection<'static, AutocommitOn> {
+    fn into_shared(
+        connection: Connection<AutocommitOn>,
+        env: &Arc<Environment<Version3>>,
+    ) -> Connection<'static, AutocommitOn> {
+        // SAFETY: The `'env` lifetime of `safe::Connection` only ensures that the environment the
+        // connection has been allocated from outlives it. Callers pass the environment
+        // `connection` has been allocated from, and the returned connection keeps it alive in its
+        // `env` field. Fields are dropped in declaration order, so `safe` is freed before the
+        // environment, even if the connection holds the last reference to it.
+        let safe = unsafe {
+            transmute::<safe::Connection<AutocommitOn>, safe::Connection<'static, AutocommitOn>>(
+                connection.safe,
+            )
+        };
+        Connection {
+            statement_cache: connection.statement_cache,
+            safe,
+            env: Some(env.clone()),
+            encodings: connection.encodings,
+            warnings: connection.warnings,
+        }
     }
 }
 
 impl <'env> Connection<'env, AutocommitOn> {
     pub fn disable_autocommit(mut self) -> std::result::Result<Connection<'env, AutocommitOff>, Self> {
         let ret = self.safe.disable_autocommit();
+        let env = self.env;
+        let encodings = self.encodings;
+        let statement_cache = self.statement_cache;
+        let warnings = self.warnings;
         match ret {
-            safe::Return::Success(value) => Ok(Connection { safe: value }),
-            safe::Return::Info(value) => Ok(Connection { safe: value }),
-            safe::Return::Error(value) => Err(Connection { safe: value })
+            safe::Return::Success(value) => Ok(Connection { statement_cache, safe: value, env, encodings, warnings }),
+            safe::Return::Info(value) => {
+                let connection = Connection { statement_cache, safe: value, env, encodings, warnings };
+                collect_warnings(&connection);
+                Ok(connection)
+            }
+            safe::Return::Error(value) => Err(Connection { statement_cache, safe: value, env, encodings, warnings })
         }
     }
 }
@@ -58,21 +477,29 @@ impl <'env> Connection<'env, AutocommitOn> {
 impl <'env> Connection<'env, AutocommitOff> {
     pub fn enable_autocommit(mut self) -> std::result::Result<Connection<'env, AutocommitOn>, Self> {
         let ret = self.safe.enable_autocommit();
+        let env = self.env;
+        let encodings = self.encodings;
+        let statement_cache = self.statement_cache;
+        let warnings = self.warnings;
         match ret {
-            safe::Return::Success(value) => Ok(Connection { safe: value }),
-            safe::Return::Info(value) => Ok(Connection { safe: value }),
-            safe::Return::Error(value) => Err(Connection { safe: value })
+            safe::Return::Success(value) => Ok(Connection { statement_cache, safe: value, env, encodings, warnings }),
+            safe::Return::Info(value) => {
+                let connection = Connection { statement_cache, safe: value, env, encodings, warnings };
+                collect_warnings(&connection);
+                Ok(connection)
+            }
+            safe::Return::Error(value) => Err(Connection { statement_cache, safe: value, env, encodings, warnings })
         }
     }
 
     pub fn commit(&mut self) -> Result<()> {
         let ret = self.safe.commit();
-        into_result_with(&self.safe, ret)
+        into_result_with(self, "SQLEndTran", ret)
     }
 
     pub fn rollback(&mut self) -> Result<()> {
         let ret = self.safe.rollback();
-        into_result_with(&self.safe, ret)
+        into_result_with(self, "SQLEndTran", ret)
     }
 }
 
@@ -89,15 +516,130 @@ impl<'env, AC: AutocommitMode> Connection<'env, AC> {
         // have to introduce a cell around `self.safe`, and be careful not to change essential
         // state in the error path. For now the trouble does not seem worth it.
         let ret = self.safe.is_read_only();
-        into_result_with(&self.safe, ret)
+        into_result_with(self, "SQLGetConnectAttr", ret)
+    }
+
+    /// `true` if the driver has detected that the connection to the server has been lost.
+    ///
+    /// This only reports the state known to the driver after the last request and does not cause
+    /// a round trip to the server, which makes it cheap enough to call before each use of a
+    /// pooled connection.
+    pub fn is_connection_dead(&self) -> Result<bool> {
+        let mut dead: ffi::SQLUINTEGER = 0;
+        let ret = match unsafe {
+            ffi::SQLGetConnectAttrW(
+                self.handle(),
+                ffi::SQL_ATTR_CONNECTION_DEAD,
+                &mut dead as *mut ffi::SQLUINTEGER as ffi::SQLPOINTER,
+                0,
+                null_mut(),
+            )
+        } {
+            ffi::SQL_SUCCESS => Return::Success(dead == SQL_CD_TRUE),
+            ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(dead == SQL_CD_TRUE),
+            ffi::SQL_ERROR => Return::Error("SQLGetConnectAttr"),
+            r => panic!("SQLGetConnectAttr returned unexpected result: {:?}", r),
+        };
+        ret.into_result(self)
+    }
+
+    /// Rolls back any open transaction and switches autocommit back on, regardless of the
+    /// autocommit mode recorded in the type. Used to clean up connections returned to a pool.
+    pub(crate) fn reset(&mut self) -> Result<()> {
+        let ret = match unsafe {
+            ffi::SQLEndTran(ffi::SQL_HANDLE_DBC, self.handle() as ffi::SQLHANDLE, ffi::SQL_ROLLBACK)
+        } {
+            ffi::SQL_SUCCESS => Return::Success(()),
+            ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(()),
+            ffi::SQL_ERROR => Return::Error("SQLEndTran"),
+            r => panic!("SQLEndTran returned unexpected result: {:?}", r),
+        };
+        ret.into_result(self)?;
+        let ret = match unsafe {
+            ffi::SQLSetConnectAttr(
+                self.handle(),
+                ffi::SQL_ATTR_AUTOCOMMIT,
+                SQL_AUTOCOMMIT_ON as ffi::SQLPOINTER,
+                ffi::SQL_IS_UINTEGER as ffi::SQLINTEGER,
+            )
+        } {
+            ffi::SQL_SUCCESS => Return::Success(()),
+            ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(()),
+            ffi::SQL_ERROR => Return::Error("SQLSetConnectAttr"),
+            r => panic!("SQLSetConnectAttr returned unexpected result: {:?}", r),
+        };
+        ret.into_result(self)
     }
 
     /// Closes the connection to the data source. If not called explicitly the disconnect will be
     /// invoked implicitly by `drop()`
     pub fn disconnect(self) -> Result<()> {
-        into_result(self.safe.disconnect())?;
+        self.statement_cache.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        let diag = Decoded::new(self.safe.as_raw(), self.encodings);
+        into_result_with(&diag, "SQLDisconnect", self.safe.disconnect())?;
         Ok(())
     }
+
+    /// Returns `sql_text` as the driver would send it to the data source, without executing it.
+    /// ODBC escape sequences, e.g. those created with the functions of the `escape` module, are
+    /// replaced by the native syntax of the data source.
+    ///
+    /// # Example
+    /// ```
+    /// # use odbc::*;
+    /// # fn doc() -> Result<()> {
+    /// let env = create_environment_v3().map_err(|e| e.unwrap())?;
+    /// let conn = env.connect("TestDataSource", "", "")?;
+    /// let sql = format!("SELECT {} FROM MOVIES", escape::function("UCASE", &["TITLE"]));
+    /// println!("{}", conn.native_sql(&sql)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn native_sql(&self, sql_text: &str) -> Result<String> {
+        native_sql(self.safe.as_raw(), sql_text, self.encodings).into_result(self)
+    }
+
+    /// Changes the encodings used by statements allocated afterwards, overriding the ones
+    /// inherited from the environment. E.g. to talk to a GBK and a UTF-8 database from the same
+    /// process.
+    pub fn set_encodings(&mut self, encodings: Encodings) {
+        self.encodings = encodings;
+        // Cached statements still use the old encodings
+        self.statement_cache.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Takes the warnings reported along with `SQL_SUCCESS_WITH_INFO` by calls on this connection,
+    /// e.g. while connecting or committing, leaving none behind. Warnings reported by statements
+    /// are kept by the statement, see `Statement::take_warnings`. Only the first
+    /// `MAX_KEPT_WARNINGS` warnings are kept, later ones are only logged and passed to the warning
+    /// handler.
+    pub fn take_warnings(&self) -> Vec<DiagnosticRecord> {
+        self.warnings.take()
+    }
+
+    /// Sets a handler called with every warning reported by this connection and by statements
+    /// allocated afterwards, in addition to keeping the warning. `None` removes the handler.
+    ///
+    /// # Example
+    /// ```
+    /// # use odbc::*;
+    /// # use std::sync::Arc;
+    /// # fn doc() -> Result<()> {
+    /// let env = create_environment_v3().map_err(|e| e.unwrap())?;
+    /// let conn = env.connect("TestDataSource", "", "")?;
+    /// conn.set_warning_handler(Some(Arc::new(|warning: &DiagnosticRecord| {
+    ///     eprintln!("ODBC warning: {}", warning);
+    /// })));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_warning_handler(&self, handler: Option<WarningHandler>) {
+        self.warnings.set_handler(handler);
+    }
+
+    pub(crate) fn statement_cache(&self) -> &Mutex<StatementCache> {
+        &self.statement_cache
+    }
 }
 
 unsafe impl<'env, AC: AutocommitMode> safe::Handle for Connection<'env, AC> {
@@ -107,3 +649,12 @@ unsafe impl<'env, AC: AutocommitMode> safe::Handle for Connection<'env, AC> {
         self.safe.as_raw() as ffi::SQLHANDLE
     }
 }
+
+// SAFETY: Connection handles are not bound to the thread which allocated them, so they may be used
+// and freed from any thread. Methods taking `&self`, e.g. allocating statements or reading
+// attributes, may be called concurrently: ODBC 3 requires drivers to be thread safe and to
+// serialize concurrent calls on the same connection handle. Changes of the connection state, like
+// ending transactions or disconnecting, require `&mut self` or `self`. The environment a
+// connection refers to is `Sync`, whether it is borrowed or kept alive by an `Arc`.
+unsafe impl<'env, AC: AutocommitMode> Send for Connection<'env, AC> {}
+unsafe impl<'env, AC: AutocommitMode> Sync for Connection<'env, AC> {}
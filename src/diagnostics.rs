@@ -1,4 +1,6 @@
-use super::{ffi, safe};
+use super::{ffi, safe, Encodings, HasEncodings, OdbcObject};
+use warnings::{HasWarnings, Warnings};
+use encoding_rs::Encoding;
 use std::{fmt, cmp};
 use std::ffi::CStr;
 use std::error::Error;
@@ -18,6 +20,7 @@ pub struct DiagnosticRecord {
     message_length: ffi::SQLSMALLINT,
     native_error: ffi::SQLINTEGER,
     message_string: String,
+    decode_error: Option<DecodeError>,
 }
 
 impl DiagnosticRecord {
@@ -33,6 +36,10 @@ impl DiagnosticRecord {
     pub fn get_native_error(&self) -> i32 {
         self.native_error
     }
+    /// The error which caused this record, if character data could not be decoded
+    pub fn decode_error(&self) -> Option<&DecodeError> {
+        self.decode_error.as_ref()
+    }
     /// constructs an empty diagnostics message.
     /// this is needed for errors where the driver doesn't return any diagnostics info.
     pub fn empty() -> DiagnosticRecord {
@@ -43,10 +50,23 @@ impl DiagnosticRecord {
             native_error: -1,
             message_length: message.len() as ffi::SQLSMALLINT,
             message_string: String::from(""),
+            decode_error: None,
         };
         rec.message[..message.len()].copy_from_slice(message);
         rec
     }
+
+    /// Constructs a diagnostic record for errors detected by this crate rather than by the driver.
+    pub(crate) fn with_message(state: &[u8; ffi::SQL_SQLSTATE_SIZE], message: &str) -> DiagnosticRecord {
+        let mut rec = DiagnosticRecord::empty();
+        rec.state[..ffi::SQL_SQLSTATE_SIZE].copy_from_slice(state);
+        let message_length = cmp::min(message.len(), MAX_DIAGNOSTIC_MESSAGE_SIZE - 1);
+        rec.message = [0u8; MAX_DIAGNOSTIC_MESSAGE_SIZE];
+        rec.message[..message_length].copy_from_slice(&message.as_bytes()[..message_length]);
+        rec.message_length = message_length as ffi::SQLSMALLINT;
+        rec.message_string = message.to_owned();
+        rec
+    }
 }
 
 impl fmt::Display for DiagnosticRecord {
@@ -74,8 +94,85 @@ impl Error for DiagnosticRecord {
     fn description(&self) -> &str {
         &self.message_string
     }
-    fn cause(&self) -> Option<& dyn Error> {
-        None
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.decode_error.as_ref().map(|e| e as &(dyn Error + 'static))
+    }
+}
+
+/// Reason for a `DecodeError`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    /// The data contains sequences which are not valid in the encoding
+    Malformed,
+    /// The data is valid, but can not be borrowed as `&str` without converting it
+    NotBorrowable,
+}
+
+/// Character data which could not be decoded under `DecodePolicy::Strict` or `DecodePolicy::Raw`
+///
+/// Returned as the cause of a `DiagnosticRecord` with state `22018` (`Malformed`) or `HY000`
+/// (`NotBorrowable`). Use `DiagnosticRecord::decode_error` to access it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    kind: DecodeErrorKind,
+    encoding: &'static Encoding,
+    bytes: Vec<u8>,
+}
+
+impl DecodeError {
+    pub(crate) fn new(kind: DecodeErrorKind, encoding: &'static Encoding, bytes: &[u8]) -> DecodeError {
+        DecodeError { kind, encoding, bytes: bytes.to_owned() }
+    }
+
+    /// Why decoding failed
+    pub fn kind(&self) -> DecodeErrorKind {
+        self.kind
+    }
+
+    /// Encoding the data has been decoded with
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    /// The data exactly as returned by the driver
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Takes ownership of the data returned by the driver
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            DecodeErrorKind::Malformed => write!(
+                f,
+                "Character data is not valid {} ({} bytes)",
+                self.encoding.name(),
+                self.bytes.len()
+            ),
+            DecodeErrorKind::NotBorrowable => write!(
+                f,
+                "Couldn't convert data to `&str`. Try `String` or `Cow<str>` instead."
+            ),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+impl From<DecodeError> for DiagnosticRecord {
+    fn from(error: DecodeError) -> DiagnosticRecord {
+        let state = match error.kind {
+            DecodeErrorKind::Malformed => b"22018",
+            DecodeErrorKind::NotBorrowable => b"HY000",
+        };
+        let mut rec = DiagnosticRecord::with_message(state, &error.to_string());
+        rec.decode_error = Some(error);
+        rec
     }
 }
 
@@ -91,12 +188,15 @@ pub trait GetDiagRec {
 
 impl<D> GetDiagRec for D
 where
-    D: safe::Diagnostics,
+    D: safe::Handle + HasEncodings,
 {
     fn get_diag_rec(&self, record_number: i16) -> Option<(DiagnosticRecord)> {
         use safe::ReturnOption::*;
+        if self.encodings().wide {
+            return get_diag_rec_wide(D::HANDLE_TYPE, safe::Handle::handle(self), record_number);
+        }
         let mut message = [0; MAX_DIAGNOSTIC_MESSAGE_SIZE];
-        match self.diagnostics(record_number, &mut message) {
+        match safe::Diagnostics::diagnostics(self, record_number, &mut message) {
             Success(result) | Info(result) => {
                 // The message could be larger than the supplied buffer, so we need to limit the message length to the buffer size.
                 let mut message_length = cmp::min(result.text_length, MAX_DIAGNOSTIC_MESSAGE_SIZE as ffi::SQLSMALLINT - 1);
@@ -109,9 +209,9 @@ where
                     native_error: result.native_error,
                     message_length,
                     message,
-                    message_string: unsafe {
-                        ::environment::OS_ENCODING.decode(&message[0..message_length as usize]).0.into_owned()
-                    },
+                    message_string: self.encodings().os
+                        .decode(&message[0..message_length as usize]).0.into_owned(),
+                    decode_error: None,
                 })
             }
             NoData(()) => None,
@@ -120,6 +220,82 @@ where
     }
 }
 
+/// Retrieves a diagnostic record using `SQLGetDiagRecW`. The message is stored UTF-8 encoded.
+fn get_diag_rec_wide(
+    handle_type: ffi::HandleType,
+    handle: ffi::SQLHANDLE,
+    record_number: i16,
+) -> Option<DiagnosticRecord> {
+    let mut state = [0u16; ffi::SQL_SQLSTATE_SIZE + 1];
+    let mut message = [0u16; MAX_DIAGNOSTIC_MESSAGE_SIZE];
+    let mut native_error: ffi::SQLINTEGER = 0;
+    let mut text_length: ffi::SQLSMALLINT = 0;
+    match unsafe {
+        ffi::SQLGetDiagRecW(
+            handle_type,
+            handle,
+            record_number,
+            state.as_mut_ptr(),
+            &mut native_error as *mut ffi::SQLINTEGER,
+            message.as_mut_ptr(),
+            message.len() as ffi::SQLSMALLINT,
+            &mut text_length as *mut ffi::SQLSMALLINT,
+        )
+    } {
+        ffi::SQL_SUCCESS | ffi::SQL_SUCCESS_WITH_INFO => {
+            let mut message_length = cmp::min(text_length as usize, MAX_DIAGNOSTIC_MESSAGE_SIZE - 1);
+            while message_length > 0 && message[message_length - 1] == 0 {
+                message_length -= 1;
+            }
+            // SQLSTATEs consist of ASCII characters only
+            let mut state_bytes = [0u8; ffi::SQL_SQLSTATE_SIZE];
+            for (byte, c) in state_bytes.iter_mut().zip(state.iter()) {
+                *byte = *c as u8;
+            }
+            let message = String::from_utf16_lossy(&message[..message_length]);
+            let mut rec = DiagnosticRecord::with_message(&state_bytes, &message);
+            rec.native_error = native_error;
+            Some(rec)
+        }
+        ffi::SQL_NO_DATA => None,
+        ffi::SQL_ERROR => panic!("Diagnostics returned error for record number {}. Record numbers have to be at least 1.", record_number),
+        r => panic!("SQLGetDiagRecW returned unexpected result: {:?}", r),
+    }
+}
+
+/// Reads the diagnostics of a raw handle, e.g. one owned by an odbc-safe type, using the
+/// encodings of the environment or connection it belongs to
+pub(crate) struct Decoded<T: OdbcObject> {
+    handle: *mut T,
+    encodings: Encodings,
+}
+
+impl<T: OdbcObject> Decoded<T> {
+    pub fn new(handle: *mut T, encodings: Encodings) -> Decoded<T> {
+        Decoded { handle, encodings }
+    }
+}
+
+unsafe impl<T: OdbcObject> safe::Handle for Decoded<T> {
+    const HANDLE_TYPE: ffi::HandleType = T::HANDLE_TYPE;
+
+    fn handle(&self) -> ffi::SQLHANDLE {
+        self.handle as ffi::SQLHANDLE
+    }
+}
+
+impl<T: OdbcObject> HasEncodings for Decoded<T> {
+    fn encodings(&self) -> Encodings {
+        self.encodings
+    }
+}
+
+impl<T: OdbcObject> HasWarnings for Decoded<T> {
+    fn warnings(&self) -> Option<&Warnings> {
+        None
+    }
+}
+
 #[cfg(test)]
 mod test {
 
@@ -133,6 +309,7 @@ mod test {
                 native_error: 0,
                 message_length: 0,
                 message_string: String::from(""),
+                decode_error: None,
             }
         }
     }
@@ -1,4 +1,4 @@
-use super::{safe, try_into_option, Environment, DiagnosticRecord, GetDiagRec, Result, Version3};
+use super::{safe, try_into_option, Environment, Error, Result, Version3};
 use ffi;
 use std::collections::HashMap;
 use std::cmp::max;
@@ -55,6 +55,7 @@ impl Environment<Version3> {
         // alloc_info iterates once over every driver to obtain the required buffer sizes
         let (max_desc, max_attr, num_drivers) = self.alloc_info(
             safe::Environment::drivers,
+            "SQLDrivers",
             ffi::SQL_FETCH_FIRST,
         )?;
 
@@ -66,6 +67,7 @@ impl Environment<Version3> {
             while let Some((desc, attr)) =
             self.get_info(
                 safe::Environment::drivers,
+                "SQLDrivers",
                 ffi::SQL_FETCH_NEXT,
                 &mut description_buffer,
                 &mut attribute_buffer,
@@ -105,7 +107,7 @@ impl Environment<Version3> {
 
         // alloc_info iterates once over every datasource to obtain the required buffer sizes
         let (max_name, max_desc, num_sources) =
-            self.alloc_info(safe::Environment::data_sources, direction)?;
+            self.alloc_info(safe::Environment::data_sources, "SQLDataSources", direction)?;
 
         let mut source_list = Vec::with_capacity(num_sources);
 
@@ -119,6 +121,7 @@ impl Environment<Version3> {
             if let Some((name, desc)) =
             self.get_info(
                 safe::Environment::data_sources,
+                "SQLDataSources",
                 direction,
                 &mut name_buffer,
                 &mut description_buffer,
@@ -135,6 +138,7 @@ impl Environment<Version3> {
             while let Some((name, desc)) =
             self.get_info(
                 safe::Environment::data_sources,
+                "SQLDataSources",
                 ffi::SQL_FETCH_NEXT,
                 &mut name_buffer,
                 &mut description_buffer,
@@ -155,16 +159,18 @@ impl Environment<Version3> {
     fn get_info<'a, 'b>(
         &mut self,
         f: SqlInfoMethod,
+        function: &'static str,
         direction: ffi::FetchOrientation,
         buf1: &'a mut [u8],
         buf2: &'b mut [u8],
     ) -> Result<Option<(::std::borrow::Cow<'a, str>, ::std::borrow::Cow<'b, str>)>> {
         let result = f(&mut self.safe, direction, buf1, buf2);
-        match try_into_option(result, self)? {
-            Some((len1, len2)) => unsafe {
+        match try_into_option(result, function, self)? {
+            Some((len1, len2)) => {
+                let encoding = self.encodings.db;
                 Ok(Some((
-                    ::environment::DB_ENCODING.decode(&buf1[0..(len1 as usize)]).0,
-                    ::environment::DB_ENCODING.decode(&buf2[0..(len2 as usize)]).0,
+                    encoding.decode(&buf1[0..(len1 as usize)]).0,
+                    encoding.decode(&buf2[0..(len2 as usize)]).0,
                 )))
             }
             None => Ok(None),
@@ -175,6 +181,7 @@ impl Environment<Version3> {
     fn alloc_info(
         &mut self,
         f: SqlInfoMethod,
+        function: &'static str,
         direction: ffi::FetchOrientation,
     ) -> Result<(ffi::SQLSMALLINT, ffi::SQLSMALLINT, usize)> {
         // In theory, we should use zero-length buffers here
@@ -205,11 +212,7 @@ impl Environment<Version3> {
                     max2 = max(max2, buf2_length_out);
                 }
                 safe::ReturnOption::NoData(()) => break,
-                safe::ReturnOption::Error(()) => {
-                    let diag = self.get_diag_rec(1).unwrap_or_else(DiagnosticRecord::empty);
-                    error!("{}", diag);
-                    return Err(diag);
-                }
+                safe::ReturnOption::Error(()) => return Err(Error::from_diagnostics(function, self)),
             }
 
             result = f(
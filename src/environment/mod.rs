@@ -1,14 +1,75 @@
 //! Implements the ODBC Environment
 mod list_data_sources;
 pub use self::list_data_sources::{DataSourceInfo, DriverInfo};
-use super::{ffi, into_result, safe, try_into_option, DiagnosticRecord, GetDiagRec, Handle, Result};
+use super::{ffi, safe, try_into_option, DiagnosticRecord, Error, GetDiagRec, Handle, Result};
+use super::diagnostics::Decoded;
+use super::result::into_result_with;
+use super::warnings::{HasWarnings, Warnings};
+use encoding_rs::{Encoding, UTF_8};
 use std;
 
 /// Environment state used to represent that environment has been set to odbc version 3
 pub type Version3 = safe::Odbc3;
 
-pub static mut OS_ENCODING: &encoding_rs::Encoding = encoding_rs::UTF_8;
-pub static mut DB_ENCODING: &encoding_rs::Encoding = encoding_rs::UTF_8;
+/// Character encodings used to convert between Rust strings and the strings exchanged with the
+/// driver
+///
+/// `os` and `db` default to UTF-8 and `decode` to `DecodePolicy::Lossy`. `wide` defaults to `true`
+/// if the `wide` feature is enabled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Encodings {
+    /// Encoding of messages generated by the driver manager and the driver, e.g. diagnostics
+    pub os: &'static Encoding,
+    /// Encoding of SQL text, column names and character data
+    pub db: &'static Encoding,
+    /// Call the wide (`W`) variants of `SQLConnect`, `SQLDriverConnect`, `SQLExecDirect`,
+    /// `SQLPrepare`, `SQLDescribeCol` and `SQLGetDiagRec`. SQL text, column names and
+    /// diagnostics are then exchanged as UTF-16 and `os` and `db` only apply to character data.
+    pub wide: bool,
+    /// How character data which can not be decoded using `db` is handled
+    pub decode: DecodePolicy,
+}
+
+/// Determines how `String`, `&str` and `Cow<str>` values are decoded from character data
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodePolicy {
+    /// Replaces malformed sequences with U+FFFD REPLACEMENT CHARACTER
+    Lossy,
+    /// Fails with a `DecodeError` carrying the raw bytes if the data contains malformed sequences
+    Strict,
+    /// Passes the data through without converting it from `db`. Fails with a `DecodeError` if it
+    /// is not valid UTF-8.
+    Raw,
+}
+
+impl Encodings {
+    /// Looks up the encodings by their labels, e.g. `"utf-8"` or `"gbk"`. Returns `None` if
+    /// either label is unknown.
+    pub fn for_labels(os_encoding: &str, db_encoding: &str) -> Option<Encodings> {
+        Some(Encodings {
+            os: Encoding::for_label(os_encoding.as_bytes())?,
+            db: Encoding::for_label(db_encoding.as_bytes())?,
+            ..Encodings::default()
+        })
+    }
+}
+
+impl Default for Encodings {
+    fn default() -> Encodings {
+        Encodings {
+            os: UTF_8,
+            db: UTF_8,
+            wide: cfg!(feature = "wide"),
+            decode: DecodePolicy::Lossy,
+        }
+    }
+}
+
+/// Implemented by handles which know the encodings of the strings exchanged through them
+pub trait HasEncodings {
+    /// Encodings used to decode diagnostics and to convert character data
+    fn encodings(&self) -> Encodings;
+}
 
 /// Handle to an ODBC Environment
 ///
@@ -17,6 +78,7 @@ pub static mut DB_ENCODING: &encoding_rs::Encoding = encoding_rs::UTF_8;
 #[derive(Debug)]
 pub struct Environment<V> {
     safe: safe::Environment<V>,
+    encodings: Encodings,
 }
 
 impl<V> Handle for Environment<V> {
@@ -52,20 +114,49 @@ impl<V: safe::Version> Environment<V> {
         let safe = match safe::Environment::new() {
             safe::Success(v) => v,
             safe::Info(v) => {
-                warn!("{}", v.get_diag_rec(1).unwrap_or_else(DiagnosticRecord::empty));
+                let diag = Decoded::new(v.as_raw(), Encodings::default()).get_diag_rec(1);
+                warn!("{}", diag.unwrap_or_else(DiagnosticRecord::empty));
                 v
             }
             safe::Error(()) => return Err(None),
         };
-        let safe = into_result(safe.declare_version())?;
-        Ok(Environment { safe })
+        let diag = Decoded::new(safe.as_raw(), Encodings::default());
+        let safe = into_result_with(&diag, "SQLSetEnvAttr", safe.declare_version())
+            .map_err(|error| Some(error.into()))?;
+        Ok(Environment { safe, encodings: Encodings::default() })
     }
 
     pub(crate) fn as_safe(&self) -> &safe::Environment<V> {
         &self.safe
     }
+
+    /// Changes the encodings used by connections created afterwards. Connections already
+    /// established keep their encodings.
+    pub fn set_encodings(&mut self, encodings: Encodings) {
+        self.encodings = encodings;
+    }
+}
+
+impl<V> HasEncodings for Environment<V> {
+    fn encodings(&self) -> Encodings {
+        self.encodings
+    }
+}
+
+// Warnings of the environment are rare and only logged
+impl<V> HasWarnings for Environment<V> {
+    fn warnings(&self) -> Option<&Warnings> {
+        None
+    }
 }
 
+// SAFETY: Environment handles are not bound to the thread which allocated them. ODBC 3 requires the
+// driver manager to be thread safe, so connections may be allocated concurrently through shared
+// references. Iterating drivers and data sources keeps state in the handle and requires
+// `&mut self`.
+unsafe impl<V: Send> Send for Environment<V> {}
+unsafe impl<V: Sync> Sync for Environment<V> {}
+
 unsafe impl<V> safe::Handle for Environment<V> {
     const HANDLE_TYPE : ffi::HandleType = ffi::SQL_HANDLE_ENV;
 
@@ -100,12 +191,17 @@ pub fn create_environment_v3()
 }
 
 
+/// Creates an ODBC Environment like `create_environment_v3`, whose connections use the encodings
+/// identified by the labels `os_encoding` and `db_encoding`.
+///
+/// # Panics
+///
+/// If either label does not name a known encoding.
 pub fn create_environment_v3_with_os_db_encoding(os_encoding: &str, db_encoding: &str)
     -> std::result::Result<Environment<Version3>, Option<DiagnosticRecord>>
 {
-    unsafe {
-        OS_ENCODING = encoding_rs::Encoding::for_label(os_encoding.as_bytes()).unwrap();
-        DB_ENCODING = encoding_rs::Encoding::for_label(db_encoding.as_bytes()).unwrap();
-    }
-    Environment::new()
+    let encodings = Encodings::for_labels(os_encoding, db_encoding).unwrap();
+    let mut env = Environment::new()?;
+    env.set_encodings(encodings);
+    Ok(env)
 }
@@ -1,3 +1,166 @@
 //! Reexport odbc-sys as ffi
 extern crate odbc_sys;
 pub use self::odbc_sys::*;
+
+// Declarations missing from odbc-sys
+
+/// Statement attribute holding the handle of the application row descriptor (ARD)
+pub const SQL_ATTR_APP_ROW_DESC: SQLINTEGER = 10010;
+/// Statement attribute holding the handle of the application parameter descriptor (APD)
+pub const SQL_ATTR_APP_PARAM_DESC: SQLINTEGER = 10011;
+
+/// Descriptor field identifiers for `SQLSetDescField`
+pub const SQL_DESC_TYPE: SQLSMALLINT = 1002;
+pub const SQL_DESC_PRECISION: SQLSMALLINT = 1005;
+pub const SQL_DESC_SCALE: SQLSMALLINT = 1006;
+pub const SQL_DESC_DATA_PTR: SQLSMALLINT = 1010;
+pub const SQL_DESC_DATETIME_INTERVAL_CODE: SQLSMALLINT = 1007;
+pub const SQL_DESC_DATETIME_INTERVAL_PRECISION: SQLSMALLINT = 26;
+/// Field identifier for `SQLColAttribute` returning the concise data type of a column
+pub const SQL_DESC_CONCISE_TYPE: SQLUSMALLINT = 2;
+
+/// Verbose data type of all interval types, stored in `SQL_DESC_TYPE`
+pub const SQL_INTERVAL: SQLSMALLINT = 10;
+
+/// Target type for `SQLGetData`, which uses type, precision and scale of the ARD record
+pub const SQL_ARD_TYPE: SQLSMALLINT = -99;
+
+#[cfg_attr(windows, link(name = "odbc32"))]
+#[cfg_attr(not(windows), link(name = "odbc"))]
+#[allow(clashing_extern_declarations)]
+extern "system" {
+    /// Returns the current setting of a statement attribute, like `SQLGetStmtAttrW`, but accepts
+    /// attributes not covered by `SqlStatementAttribute`.
+    pub fn SQLGetStmtAttr(
+        handle: SQLHSTMT,
+        attribute: SQLINTEGER,
+        value_ptr: SQLPOINTER,
+        buffer_length: SQLINTEGER,
+        string_length_ptr: *mut SQLINTEGER,
+    ) -> SQLRETURN;
+
+    /// Sets the value of a single field of a descriptor record.
+    pub fn SQLSetDescField(
+        descriptor_handle: SQLHDESC,
+        record_number: SQLSMALLINT,
+        field_identifier: SQLSMALLINT,
+        value_ptr: SQLPOINTER,
+        buffer_length: SQLINTEGER,
+    ) -> SQLRETURN;
+
+    /// `SQLBindParameter` with the data types passed as integers, since `SqlDataType` can not
+    /// express the interval SQL types.
+    #[link_name = "SQLBindParameter"]
+    pub fn SQLBindParameterWithTypes(
+        hstmt: SQLHSTMT,
+        parameter_number: SQLUSMALLINT,
+        input_output_type: InputOutput,
+        value_type: SQLSMALLINT,
+        parameter_type: SQLSMALLINT,
+        column_size: SQLULEN,
+        decimal_digits: SQLSMALLINT,
+        parameter_value_ptr: SQLPOINTER,
+        buffer_length: SQLLEN,
+        str_len_or_ind_ptr: *mut SQLLEN,
+    ) -> SQLRETURN;
+
+    /// Returns descriptor information for a column in a result set, like `SQLColAttributeW`, but
+    /// returns character attributes in the narrow encoding.
+    pub fn SQLColAttribute(
+        statement_handle: SQLHSTMT,
+        column_number: SQLUSMALLINT,
+        field_identifier: SQLUSMALLINT,
+        character_attribute_ptr: SQLPOINTER,
+        buffer_length: SQLSMALLINT,
+        string_length_ptr: *mut SQLSMALLINT,
+        numeric_attribute_ptr: *mut SQLLEN,
+    ) -> SQLRETURN;
+
+    /// Returns the number of parameter markers in an SQL statement.
+    pub fn SQLNumParams(statement_handle: SQLHSTMT, param_count_ptr: *mut SQLSMALLINT) -> SQLRETURN;
+
+    /// `SQLDescribeParam` with the data type returned as integer, since `SqlDataType` can not
+    /// express all types a driver may report.
+    #[link_name = "SQLDescribeParam"]
+    pub fn SQLDescribeParamWithType(
+        statement_handle: SQLHSTMT,
+        parameter_number: SQLUSMALLINT,
+        data_type_ptr: *mut SQLSMALLINT,
+        parameter_size_ptr: *mut SQLULEN,
+        decimal_digits_ptr: *mut SQLSMALLINT,
+        nullable_ptr: *mut SQLSMALLINT,
+    ) -> SQLRETURN;
+
+    /// `SQLGetData` with the target type passed as integer, since `SqlCDataType` can not express
+    /// `SQL_ARD_TYPE`.
+    #[link_name = "SQLGetData"]
+    pub fn SQLGetDataWithTargetType(
+        statement_handle: SQLHSTMT,
+        col_or_param_num: SQLUSMALLINT,
+        target_type: SQLSMALLINT,
+        target_value_ptr: SQLPOINTER,
+        buffer_length: SQLLEN,
+        str_len_or_ind_ptr: *mut SQLLEN,
+    ) -> SQLRETURN;
+
+    /// Returns the SQL string as modified by the driver, without executing it.
+    pub fn SQLNativeSql(
+        connection_handle: SQLHDBC,
+        in_statement_text: *const SQLCHAR,
+        text_length_1: SQLINTEGER,
+        out_statement_text: *mut SQLCHAR,
+        buffer_length: SQLINTEGER,
+        text_length_2_ptr: *mut SQLINTEGER,
+    ) -> SQLRETURN;
+
+    /// Returns the SQL string as modified by the driver, without executing it. Lengths are in
+    /// characters.
+    pub fn SQLNativeSqlW(
+        connection_handle: SQLHDBC,
+        in_statement_text: *const SQLWCHAR,
+        text_length_1: SQLINTEGER,
+        out_statement_text: *mut SQLWCHAR,
+        buffer_length: SQLINTEGER,
+        text_length_2_ptr: *mut SQLINTEGER,
+    ) -> SQLRETURN;
+}
+
+/// Converts a data type reported by the driver into `SqlDataType`. Types not covered by
+/// `SqlDataType`, e.g. intervals, are converted into `SQL_UNKNOWN_TYPE`.
+pub fn sql_data_type(data_type: SQLSMALLINT) -> SqlDataType {
+    match data_type {
+        1 => SQL_CHAR,
+        2 => SQL_NUMERIC,
+        3 => SQL_DECIMAL,
+        4 => SQL_INTEGER,
+        5 => SQL_SMALLINT,
+        6 => SQL_FLOAT,
+        7 => SQL_REAL,
+        8 => SQL_DOUBLE,
+        9 => SQL_DATETIME,
+        10 => SQL_EXT_TIME_OR_INTERVAL,
+        11 => SQL_EXT_TIMESTAMP,
+        12 => SQL_VARCHAR,
+        91 => SQL_DATE,
+        92 => SQL_TIME,
+        93 => SQL_TIMESTAMP,
+        -1 => SQL_EXT_LONGVARCHAR,
+        -2 => SQL_EXT_BINARY,
+        -3 => SQL_EXT_VARBINARY,
+        -4 => SQL_EXT_LONGVARBINARY,
+        -5 => SQL_EXT_BIGINT,
+        -6 => SQL_EXT_TINYINT,
+        -7 => SQL_EXT_BIT,
+        -8 => SQL_EXT_WCHAR,
+        -9 => SQL_EXT_WVARCHAR,
+        -10 => SQL_EXT_WLONGVARCHAR,
+        -11 => SQL_EXT_GUID,
+        -150 => SQL_SS_VARIANT,
+        -151 => SQL_SS_UDT,
+        -152 => SQL_SS_XML,
+        -153 => SQL_SS_TABLE,
+        -154 => SQL_SS_TIME2,
+        -155 => SQL_SS_TIMESTAMPOFFSET,
+        _ => SQL_UNKNOWN_TYPE,
+    }
+}
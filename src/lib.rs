@@ -23,32 +23,75 @@
 //!
 //! [ODBC State Transitions]
 //! (https://docs.microsoft.com/en-us/sql/odbc/reference/appendixes/appendix-b-odbc-state-transition-tables)
+//!
+//! # Thread Safety
+//!
+//! ODBC 3 requires drivers to be thread safe. `Environment` and `Connection` are therefore `Send`
+//! and `Sync`. Concurrent calls on the same handle are serialized by the driver manager or the
+//! driver. Sharing a `Connection` between threads is mostly useful to allocate a `Statement` for
+//! each thread. `Statement` and `Cursor` are `Send`, but not `Sync`, since diagnostics and
+//! cursor position are part of the state of the statement handle and interleaving calls from
+//! different threads would mix them up.
+//!
+//! [Multithreading]
+//! (https://docs.microsoft.com/en-us/sql/odbc/reference/develop-app/multithreading)
 
 #[macro_use]
 extern crate log;
 pub extern crate odbc_safe;
-extern crate encoding_rs;
+pub extern crate encoding_rs;
+#[cfg(feature = "r2d2")]
+extern crate r2d2;
+#[cfg(feature = "async")]
+extern crate tokio;
+#[cfg(feature = "async")]
+extern crate futures_core;
+#[cfg(feature = "rust_decimal")]
+extern crate rust_decimal;
+#[cfg(feature = "bigdecimal")]
+extern crate bigdecimal;
+#[cfg(feature = "uuid")]
+extern crate uuid;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "time")]
+extern crate time;
 
 pub mod ffi;
 
-pub use diagnostics::{DiagnosticRecord, GetDiagRec};
-pub use result::Result;
+pub use diagnostics::{DecodeError, DecodeErrorKind, DiagnosticRecord, GetDiagRec};
+pub use sql_state::{SqlState, SqlStateClass};
+pub use result::{Error, Result};
+pub use warnings::{WarningHandler, MAX_KEPT_WARNINGS};
 pub use environment::*;
-pub use connection::Connection;
+pub use connection::{ConnectAsync, Connection};
+pub use pool::{Pool, PoolConfig, PooledConnection, PooledTransaction};
+#[cfg(feature = "r2d2")]
+pub use pool::OdbcConnectionManager;
+#[cfg(feature = "async")]
+pub use asynchronous::{AsyncConnection, AsyncStatement, RowStream};
 pub use statement::*;
 
 use odbc_object::OdbcObject;
 use raii::Raii;
-use result::{Return, into_result, try_into_option};
+use result::{Return, try_into_option};
+use sql_text::SqlText;
 pub use odbc_safe as safe;
 
 mod odbc_object;
+mod backoff;
 mod raii;
 mod diagnostics;
+mod sql_state;
 mod result;
+mod warnings;
+mod sql_text;
 mod environment;
 mod connection;
 mod statement;
+mod pool;
+#[cfg(feature = "async")]
+mod asynchronous;
 
 
 /// Reflects the ability of a type to expose a valid handle
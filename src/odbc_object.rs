@@ -20,3 +20,8 @@ unsafe impl OdbcObject for ffi::Stmt {
     const HANDLE_TYPE: ffi::HandleType = ffi::SQL_HANDLE_STMT;
     type Parent = ffi::Dbc;
 }
+
+unsafe impl OdbcObject for ffi::Desc {
+    const HANDLE_TYPE: ffi::HandleType = ffi::SQL_HANDLE_DESC;
+    type Parent = ffi::Dbc;
+}
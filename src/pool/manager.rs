@@ -0,0 +1,76 @@
+//! Integration with the `r2d2` connection pool
+use {Connection, Data, Environment, Error, Statement, Version3};
+use odbc_safe::AutocommitOn;
+use r2d2;
+use std::sync::Arc;
+
+/// An `r2d2::ManageConnection` opening connections to a data source using a connection string
+///
+/// # Example
+///
+/// ```
+/// # extern crate odbc;
+/// # extern crate r2d2;
+/// # use odbc::*;
+/// # fn doc() -> std::result::Result<(), Box<std::error::Error>> {
+/// let env = create_environment_v3().map_err(|e| e.unwrap())?;
+/// let manager = OdbcConnectionManager::new(env, "dsn=TestDataSource;Uid=;Pwd=;");
+/// let pool = r2d2::Pool::new(manager)?;
+/// let conn = pool.get()?;
+/// let stmt = Statement::with_parent(&conn)?;
+/// # Ok(())
+/// # }
+/// # fn main() {}
+/// ```
+#[derive(Debug)]
+pub struct OdbcConnectionManager {
+    env: Arc<Environment<Version3>>,
+    connection_string: String,
+    test_query: String,
+}
+
+impl OdbcConnectionManager {
+    /// Creates a manager opening its connections with `env`. `SELECT 1` is used to check if
+    /// connections are valid.
+    ///
+    /// # Arguments
+    /// * `env` - Environment used to open the connections. Freed with the last connection.
+    /// * `connection_str` - Connection string passed to `SQLDriverConnect`
+    pub fn new(env: Environment<Version3>, connection_str: &str) -> OdbcConnectionManager {
+        OdbcConnectionManager {
+            env: Arc::new(env),
+            connection_string: connection_str.to_owned(),
+            test_query: "SELECT 1".to_owned(),
+        }
+    }
+
+    /// Replaces the query executed by `is_valid`, e.g. with `SELECT 1 FROM DUAL` for data sources
+    /// which do not support `SELECT` without a table.
+    pub fn with_test_query(mut self, test_query: &str) -> OdbcConnectionManager {
+        self.test_query = test_query.to_owned();
+        self
+    }
+}
+
+impl r2d2::ManageConnection for OdbcConnectionManager {
+    type Connection = Connection<'static, AutocommitOn>;
+    type Error = Error;
+
+    fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        Environment::connect_with_connection_string_shared(&self.env, &self.connection_string)
+    }
+
+    fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        let stmt = Statement::with_parent(conn)?;
+        if let Data(stmt) = stmt.exec_direct(&self.test_query)? {
+            stmt.close_cursor()?;
+        }
+        Ok(())
+    }
+
+    fn has_broken(&self, conn: &mut Self::Connection) -> bool {
+        // Drivers which do not support `SQL_ATTR_CONNECTION_DEAD` fail the check. Such connections
+        // are still validated by `is_valid` on checkout.
+        conn.is_connection_dead().unwrap_or(false)
+    }
+}
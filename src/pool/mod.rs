@@ -0,0 +1,379 @@
+//! Implements a pool of connections to a single data source
+#[cfg(feature = "r2d2")]
+mod manager;
+#[cfg(feature = "r2d2")]
+pub use self::manager::OdbcConnectionManager;
+use super::{Connection, DiagnosticRecord, Environment, Error, Result, Version3};
+use odbc_safe::{AutocommitMode, AutocommitOff, AutocommitOn};
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Settings governing the behaviour of a `Pool`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolConfig {
+    /// Maximum number of connections managed by the pool, both idle and checked out.
+    pub max_size: usize,
+    /// Idle connections are closed once they have not been used for this long.
+    pub idle_timeout: Option<Duration>,
+    /// Connections are closed once they are older than this, regardless of how they have been
+    /// used.
+    pub max_lifetime: Option<Duration>,
+    /// Verify with `Connection::is_connection_dead` that an idle connection is still usable
+    /// before handing it out. Connections to drivers which do not support the check are assumed
+    /// to be alive.
+    pub test_on_checkout: bool,
+    /// How long `Pool::get` waits for a connection to become available if `max_size`
+    /// connections are already checked out.
+    pub connection_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> PoolConfig {
+        PoolConfig {
+            max_size: 10,
+            idle_timeout: Some(Duration::from_secs(10 * 60)),
+            max_lifetime: Some(Duration::from_secs(30 * 60)),
+            test_on_checkout: true,
+            connection_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+struct IdleConnection {
+    connection: Connection<'static, AutocommitOn>,
+    created: Instant,
+    idle_since: Instant,
+}
+
+struct PoolState {
+    idle: Vec<IdleConnection>,
+    // Number of open connections, idle and checked out.
+    open: usize,
+}
+
+/// Keeps idle connections to a data source around, so they can be reused instead of opening a new
+/// one each time.
+///
+/// The pool owns its `Environment`, so the connections it hands out are not bound to the lifetime
+/// of a borrowed environment. Connections are opened lazily.
+///
+/// # Example
+///
+/// ```
+/// # use odbc::*;
+/// # fn doc() -> Result<()> {
+/// let env = create_environment_v3().map_err(|e| e.unwrap())?;
+/// let pool = Pool::new(env, "dsn=TestDataSource;Uid=;Pwd=;");
+/// {
+///     let conn = pool.get()?;
+///     let stmt = Statement::with_parent(&conn)?;
+///     stmt.exec_direct("SELECT TITLE FROM MOVIES")?;
+/// } // `conn` is returned to the pool here
+/// # Ok(())
+/// # }
+/// ```
+pub struct Pool {
+    state: Mutex<PoolState>,
+    available: Condvar,
+    config: PoolConfig,
+    connection_string: String,
+    env: Arc<Environment<Version3>>,
+}
+
+impl Pool {
+    /// Creates a pool with the default `PoolConfig`. No connection is opened until the first call
+    /// to `get`.
+    ///
+    /// # Arguments
+    /// * `env` - Environment used to open the connections. It is freed together with the pool.
+    /// * `connection_str` - Connection string passed to `SQLDriverConnect`
+    pub fn new(env: Environment<Version3>, connection_str: &str) -> Pool {
+        Pool::with_config(env, connection_str, PoolConfig::default())
+    }
+
+    /// Creates a pool with the specified settings. No connection is opened until the first call
+    /// to `get`.
+    pub fn with_config(env: Environment<Version3>, connection_str: &str, config: PoolConfig) -> Pool {
+        Pool {
+            state: Mutex::new(PoolState {
+                idle: Vec::new(),
+                open: 0,
+            }),
+            available: Condvar::new(),
+            config,
+            connection_string: connection_str.to_owned(),
+            env: Arc::new(env),
+        }
+    }
+
+    /// Settings this pool has been created with
+    pub fn config(&self) -> &PoolConfig {
+        &self.config
+    }
+
+    /// Number of connections currently held by the pool, idle and checked out.
+    pub fn open_connections(&self) -> usize {
+        self.lock().open
+    }
+
+    /// Number of idle connections waiting to be checked out.
+    pub fn idle_connections(&self) -> usize {
+        self.lock().idle.len()
+    }
+
+    /// Checks out a connection. The connection is returned to the pool, once the returned guard
+    /// is dropped.
+    ///
+    /// Idle connections exceeding the idle timeout or lifetime of the pool are closed. If no idle
+    /// connection is left a new one is opened, unless `max_size` connections are already checked
+    /// out. In that case this method blocks until another connection is returned, or fails with
+    /// SQLSTATE `HYT00` once `connection_timeout` has passed.
+    pub fn get(&self) -> Result<PooledConnection<'_>> {
+        let deadline = Instant::now() + self.config.connection_timeout;
+        let mut state = self.lock();
+        loop {
+            let expired = self.take_expired(&mut state);
+            if !expired.is_empty() {
+                // Do not block other threads while disconnecting
+                drop(state);
+                drop(expired);
+                state = self.lock();
+            }
+            while let Some(idle) = state.idle.pop() {
+                if !self.config.test_on_checkout {
+                    return Ok(self.checkout(idle.connection, idle.created));
+                }
+                // Do not block other threads while talking to the driver
+                drop(state);
+                if is_alive(&idle.connection) {
+                    return Ok(self.checkout(idle.connection, idle.created));
+                }
+                debug!("Discarding dead pooled connection");
+                drop(idle);
+                state = self.lock();
+                state.open -= 1;
+            }
+
+            if state.open < self.config.max_size {
+                state.open += 1;
+                drop(state);
+                return match Environment::connect_with_connection_string_shared(
+                    &self.env,
+                    &self.connection_string,
+                ) {
+                    Ok(connection) => Ok(self.checkout(connection, Instant::now())),
+                    Err(diag) => {
+                        self.lock().open -= 1;
+                        self.available.notify_one();
+                        Err(diag)
+                    }
+                };
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(DiagnosticRecord::with_message(
+                    b"HYT00",
+                    "Timed out waiting for a connection from the pool.",
+                ).into());
+            }
+            state = self
+                .available
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    fn checkout(&self, connection: Connection<'static, AutocommitOn>, created: Instant) -> PooledConnection<'_> {
+        PooledConnection {
+            connection: Some(connection),
+            created,
+            pool: self,
+        }
+    }
+
+    /// Removes the idle connections exceeding the idle timeout or lifetime from `state`. They are
+    /// no longer counted as open, but are only closed once dropped.
+    fn take_expired(&self, state: &mut PoolState) -> Vec<IdleConnection> {
+        let (expired, idle): (Vec<_>, Vec<_>) =
+            mem::take(&mut state.idle).into_iter().partition(|idle| self.is_expired(idle));
+        state.idle = idle;
+        state.open -= expired.len();
+        expired
+    }
+
+    fn is_expired(&self, idle: &IdleConnection) -> bool {
+        let idle_too_long = match self.config.idle_timeout {
+            Some(timeout) => idle.idle_since.elapsed() >= timeout,
+            None => false,
+        };
+        idle_too_long || self.is_too_old(idle.created)
+    }
+
+    fn is_too_old(&self, created: Instant) -> bool {
+        match self.config.max_lifetime {
+            Some(lifetime) => created.elapsed() >= lifetime,
+            None => false,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        // The state is consistent between statements, so it is fine to carry on after a panic
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn put_back(&self, mut connection: Connection<'static, AutocommitOn>, created: Instant) {
+        let reusable = match connection.reset() {
+            Ok(()) => !self.is_too_old(created),
+            Err(diag) => {
+                warn!("Could not reset pooled connection: {}", diag);
+                false
+            }
+        };
+        if reusable {
+            self.lock().idle.push(IdleConnection {
+                connection,
+                created,
+                idle_since: Instant::now(),
+            });
+            self.available.notify_one();
+        } else {
+            self.discard(connection);
+        }
+    }
+
+    /// Closes a checked out connection which can not be reused
+    fn discard<AC: AutocommitMode>(&self, connection: Connection<'static, AC>) {
+        drop(connection);
+        self.lock().open -= 1;
+        self.available.notify_one();
+    }
+}
+
+/// `false` if the driver reports the connection to be dead
+fn is_alive(connection: &Connection<'static, AutocommitOn>) -> bool {
+    match connection.is_connection_dead() {
+        Ok(dead) => !dead,
+        // Drivers which do not know `SQL_ATTR_CONNECTION_DEAD` can not tell
+        Err(ref error) if error.sql_state() == "HY092" || error.sql_state() == "HYC00" => true,
+        Err(_) => false,
+    }
+}
+
+/// A connection checked out from a `Pool`.
+///
+/// Dereferences to `Connection`. Use `transaction` to switch off autocommit. Any open transaction
+/// is rolled back and autocommit is switched on again once the guard is dropped and the connection
+/// is returned to the pool.
+pub struct PooledConnection<'p> {
+    // Only `None` while being returned to the pool
+    connection: Option<Connection<'static, AutocommitOn>>,
+    created: Instant,
+    pool: &'p Pool,
+}
+
+impl<'p> PooledConnection<'p> {
+    /// Switches off autocommit, so statements executed on the returned guard are only made
+    /// permanent by `Connection::commit`. Once the guard is dropped, changes which have not been
+    /// committed are rolled back and the connection is returned to the pool.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use odbc::*;
+    /// # fn doc() -> Result<()> {
+    /// let env = create_environment_v3().map_err(|e| e.unwrap())?;
+    /// let pool = Pool::new(env, "dsn=TestDataSource;Uid=;Pwd=;");
+    /// let mut transaction = pool.get()?.transaction()?;
+    /// Statement::with_parent(&transaction)?.exec_direct("DELETE FROM MOVIES WHERE YEAR < 1900")?;
+    /// transaction.commit()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn transaction(mut self) -> Result<PooledTransaction<'p>> {
+        let connection = self.connection.take().unwrap();
+        match connection.disable_autocommit() {
+            Ok(connection) => Ok(PooledTransaction {
+                connection: Some(connection),
+                created: self.created,
+                pool: self.pool,
+            }),
+            Err(connection) => {
+                let error = Error::from_diagnostics("SQLSetConnectAttr", &connection);
+                self.connection = Some(connection);
+                Err(error)
+            }
+        }
+    }
+}
+
+impl<'p> Deref for PooledConnection<'p> {
+    type Target = Connection<'static, AutocommitOn>;
+
+    fn deref(&self) -> &Self::Target {
+        self.connection.as_ref().unwrap()
+    }
+}
+
+impl<'p> DerefMut for PooledConnection<'p> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.connection.as_mut().unwrap()
+    }
+}
+
+impl<'p> Drop for PooledConnection<'p> {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            self.pool.put_back(connection, self.created);
+        }
+    }
+}
+
+/// A connection checked out from a `Pool` with autocommit switched off, see
+/// `PooledConnection::transaction`.
+///
+/// Dereferences to `Connection`. Changes which have not been committed are rolled back once the
+/// guard is dropped, before the connection is returned to the pool.
+pub struct PooledTransaction<'p> {
+    // Only `None` while being returned to the pool
+    connection: Option<Connection<'static, AutocommitOff>>,
+    created: Instant,
+    pool: &'p Pool,
+}
+
+impl<'p> Deref for PooledTransaction<'p> {
+    type Target = Connection<'static, AutocommitOff>;
+
+    fn deref(&self) -> &Self::Target {
+        self.connection.as_ref().unwrap()
+    }
+}
+
+impl<'p> DerefMut for PooledTransaction<'p> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.connection.as_mut().unwrap()
+    }
+}
+
+impl<'p> Drop for PooledTransaction<'p> {
+    fn drop(&mut self) {
+        if let Some(mut connection) = self.connection.take() {
+            // Switching autocommit on commits the open transaction, so it is rolled back first
+            if let Err(diag) = connection.rollback() {
+                warn!("Could not roll back pooled connection: {}", diag);
+                self.pool.discard(connection);
+                return;
+            }
+            match connection.enable_autocommit() {
+                Ok(connection) => self.pool.put_back(connection, self.created),
+                Err(connection) => {
+                    warn!("Could not switch autocommit on for pooled connection");
+                    self.pool.discard(connection);
+                }
+            }
+        }
+    }
+}
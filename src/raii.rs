@@ -1,19 +1,39 @@
-use super::{ffi, safe, DiagnosticRecord, GetDiagRec, Handle, OdbcObject, Return};
+use super::{ffi, safe, DiagnosticRecord, Encodings, GetDiagRec, Handle, HasEncodings, OdbcObject, Return};
+use statement::StatementCache;
+use warnings::{HasWarnings, WarningHandler, Warnings};
 use std::ptr::null_mut;
 use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
 
 /// Wrapper around handle types which ensures the wrapped value is always valid.
 ///
 /// Resource Acquisition Is Initialization
-#[derive(Debug)]
 pub struct Raii<'p, T: OdbcObject> {
     //Invariant: Should always point to a valid odbc Object
     handle: *mut T,
     // we use phantom data to tell the borrow checker that we need to keep the data source alive
     // for the lifetime of the handle
     parent: PhantomData<&'p ()>,
+    // Keeps the parent alive for handles which do not borrow it. Only released after the handle
+    // has been freed in `drop`.
+    _owner: Option<Arc<dyn Send + Sync>>,
+    // Inherited from the parent, used to convert strings exchanged through this handle
+    encodings: Encodings,
+    // Statement cache of the parent connection and the text the statement has been prepared
+    // with. Set for statements created by `Connection::prepare_cached`, which are handed back to
+    // the cache instead of being freed.
+    cache: Option<(&'p Mutex<StatementCache>, String)>,
+    // Warnings reported for the handle, until taken by the application
+    warnings: Warnings,
 }
 
+// SAFETY: Statement handles are not bound to the thread which allocated them, so they may be used
+// and freed from any thread, as long as they are not used concurrently. `Statement` only calls
+// into its handle through `&mut self`, or through `&self` if it is not `Sync`, which holds as long
+// as `Raii` is not `Sync`: diagnostics and cursor position are part of the state of the handle.
+// A parent kept alive by `_owner` is `Send + Sync` itself.
+unsafe impl<'p> Send for Raii<'p, ffi::Stmt> {}
+
 impl<'p, T: OdbcObject> Handle for Raii<'p, T> {
     type To = T;
     unsafe fn handle(&self) -> *mut T {
@@ -21,6 +41,18 @@ impl<'p, T: OdbcObject> Handle for Raii<'p, T> {
     }
 }
 
+impl<'p, T: OdbcObject> HasEncodings for Raii<'p, T> {
+    fn encodings(&self) -> Encodings {
+        self.encodings
+    }
+}
+
+impl<'p, T: OdbcObject> HasWarnings for Raii<'p, T> {
+    fn warnings(&self) -> Option<&Warnings> {
+        Some(&self.warnings)
+    }
+}
+
 unsafe impl<'p, T: OdbcObject> safe::Handle for Raii<'p, T> {
     const HANDLE_TYPE: ffi::HandleType = T::HANDLE_TYPE;
 
@@ -31,6 +63,11 @@ unsafe impl<'p, T: OdbcObject> safe::Handle for Raii<'p, T> {
 
 impl<'p, T: OdbcObject> Drop for Raii<'p, T> {
     fn drop(&mut self) {
+        if let Some((cache, sql)) = self.cache.take() {
+            if StatementCache::recycle(cache, sql, self.handle as ffi::SQLHSTMT) {
+                return;
+            }
+        }
         match unsafe { ffi::SQLFreeHandle(T::HANDLE_TYPE, self.handle() as ffi::SQLHANDLE) } {
             ffi::SQL_SUCCESS => (),
             ffi::SQL_ERROR => {
@@ -45,26 +82,91 @@ impl<'p, T: OdbcObject> Drop for Raii<'p, T> {
 impl<'p, T: OdbcObject> Raii<'p, T> {
     pub fn with_parent<P>(parent: &'p P) -> Return<Self>
     where
-        P: Handle<To = T::Parent>,
+        P: Handle<To = T::Parent> + HasEncodings + HasWarnings,
     {
-        let mut handle: ffi::SQLHANDLE = null_mut();
-        match unsafe {
-            ffi::SQLAllocHandle(
-                T::HANDLE_TYPE,
+        unsafe {
+            Self::allocate(
                 parent.handle() as ffi::SQLHANDLE,
-                &mut handle as *mut ffi::SQLHANDLE,
+                None,
+                parent.encodings(),
+                inherited_handler(parent),
             )
-        } {
+        }
+    }
+
+    unsafe fn allocate(
+        parent: ffi::SQLHANDLE,
+        owner: Option<Arc<dyn Send + Sync>>,
+        encodings: Encodings,
+        handler: Option<WarningHandler>,
+    ) -> Return<Self> {
+        let mut handle: ffi::SQLHANDLE = null_mut();
+        match ffi::SQLAllocHandle(T::HANDLE_TYPE, parent, &mut handle as *mut ffi::SQLHANDLE) {
             ffi::SQL_SUCCESS => Return::Success(Raii {
                 handle: handle as *mut T,
                 parent: PhantomData,
+                _owner: owner,
+                encodings,
+                cache: None,
+                warnings: Warnings::new(handler),
             }),
             ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(Raii {
                 handle: handle as *mut T,
                 parent: PhantomData,
+                _owner: owner,
+                encodings,
+                cache: None,
+                warnings: Warnings::new(handler),
             }),
-            ffi::SQL_ERROR => Return::Error,
+            ffi::SQL_ERROR => Return::Error("SQLAllocHandle"),
             _ => panic!("SQLAllocHandle returned unexpected result"),
         }
     }
 }
+
+impl<'p> Raii<'p, ffi::Stmt> {
+    /// Takes ownership of a statement handle allocated earlier, e.g. one kept by a
+    /// `StatementCache`. The handle must be valid and must not be owned by anyone else.
+    pub(crate) unsafe fn from_raw(
+        handle: ffi::SQLHSTMT,
+        encodings: Encodings,
+        handler: Option<WarningHandler>,
+    ) -> Self {
+        Raii {
+            handle,
+            parent: PhantomData,
+            _owner: None,
+            encodings,
+            cache: None,
+            warnings: Warnings::new(handler),
+        }
+    }
+
+    /// Takes the warnings reported for the statement so far
+    pub(crate) fn take_warnings(&self) -> Vec<DiagnosticRecord> {
+        self.warnings.take()
+    }
+
+    /// Hands the statement back to `cache` instead of freeing it, once dropped
+    pub(crate) fn set_cache(&mut self, cache: &'p Mutex<StatementCache>, sql: String) {
+        self.cache = Some((cache, sql));
+    }
+}
+
+impl<T: OdbcObject> Raii<'static, T> {
+    /// Allocates a handle which holds a reference to its parent instead of borrowing it.
+    pub fn with_shared_parent<P>(parent: &Arc<P>) -> Return<Self>
+    where
+        P: Handle<To = T::Parent> + HasEncodings + HasWarnings + Send + Sync + 'static,
+    {
+        let owner: Arc<dyn Send + Sync> = parent.clone();
+        let encodings = parent.encodings();
+        let handler = inherited_handler(&**parent);
+        unsafe { Self::allocate(parent.handle() as ffi::SQLHANDLE, Some(owner), encodings, handler) }
+    }
+}
+
+/// Warning handler of `parent`, which child handles report their warnings to as well
+fn inherited_handler<P: HasWarnings>(parent: &P) -> Option<WarningHandler> {
+    parent.warnings().and_then(Warnings::handler)
+}
@@ -1,135 +1,212 @@
 //! Result types to enabling handling of ODBC Errors
-use super::{DiagnosticRecord, GetDiagRec};
+use super::{DecodeError, DiagnosticRecord, GetDiagRec, SqlState};
+use warnings::{collect_warnings, HasWarnings};
 use safe;
 use std;
+use std::error;
+use std::fmt;
 
 /// Result type returned by most functions in this crate
-pub type Result<T> = std::result::Result<T, DiagnosticRecord>;
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Error returned by most functions in this crate
+///
+/// Holds all diagnostic records the driver reported for the failed call, together with the name
+/// of the ODBC function which failed. Some drivers put the most useful message into a later
+/// record, so `Display` shows all of them. The accessors like `get_raw_state` refer to the first
+/// record.
+pub struct Error {
+    function: Option<&'static str>,
+    // Never empty
+    records: Vec<DiagnosticRecord>,
+}
+
+impl Error {
+    /// Collects all diagnostic records of `odbc_object` after `function` failed
+    pub(crate) fn from_diagnostics<O: GetDiagRec>(function: &'static str, odbc_object: &O) -> Error {
+        let mut records = Vec::new();
+        while let Some(record) = odbc_object.get_diag_rec(records.len() as i16 + 1) {
+            error!("{}", record);
+            records.push(record);
+        }
+        if records.is_empty() {
+            records.push(DiagnosticRecord::empty());
+        }
+        Error { function: Some(function), records }
+    }
+
+    /// Name of the ODBC function which failed, e.g. `"SQLExecDirect"`. `None` for errors detected
+    /// by this crate rather than by the driver.
+    pub fn function(&self) -> Option<&'static str> {
+        self.function
+    }
+
+    /// All diagnostic records reported for the failed call, in the order of their record numbers
+    pub fn records(&self) -> &[DiagnosticRecord] {
+        &self.records
+    }
+
+    /// Takes ownership of all diagnostic records reported for the failed call
+    pub fn into_records(self) -> Vec<DiagnosticRecord> {
+        self.records
+    }
+
+    /// The first diagnostic record
+    pub fn record(&self) -> &DiagnosticRecord {
+        &self.records[0]
+    }
+
+    /// Raw state of the first record, see `DiagnosticRecord::get_raw_state`
+    pub fn get_raw_state(&self) -> &[u8] {
+        self.record().get_raw_state()
+    }
+
+    /// Raw message of the first record, see `DiagnosticRecord::get_raw_message`
+    pub fn get_raw_message(&self) -> &[u8] {
+        self.record().get_raw_message()
+    }
+
+    /// Native error of the first record, see `DiagnosticRecord::get_native_error`
+    pub fn get_native_error(&self) -> i32 {
+        self.record().get_native_error()
+    }
+
+    /// SQLSTATE of the first record, see `DiagnosticRecord::sql_state`
+    pub fn sql_state(&self) -> SqlState {
+        self.record().sql_state()
+    }
+
+    /// The error which caused the first record, if character data could not be decoded
+    pub fn decode_error(&self) -> Option<&DecodeError> {
+        self.record().decode_error()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(function) = self.function {
+            write!(f, "{} failed: ", function)?;
+        }
+        write!(f, "{}", self.records[0])?;
+        for record in &self.records[1..] {
+            write!(f, "\n{}", record)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        // The records themselves are part of the message already
+        self.records[0].decode_error().map(|e| e as &(dyn error::Error + 'static))
+    }
+}
+
+impl From<DiagnosticRecord> for Error {
+    fn from(record: DiagnosticRecord) -> Error {
+        Error { function: None, records: vec![record] }
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(error: DecodeError) -> Error {
+        DiagnosticRecord::from(error).into()
+    }
+}
+
+/// Allows using `?` in functions which still return the first `DiagnosticRecord` only
+impl From<Error> for DiagnosticRecord {
+    fn from(error: Error) -> DiagnosticRecord {
+        error.records.into_iter().next().unwrap()
+    }
+}
 
 #[must_use]
 pub enum Return<T> {
     Success(T),
     SuccessWithInfo(T),
-    Error,
+    /// Holds the name of the ODBC function which failed
+    Error(&'static str),
 }
 
 impl<T> Return<T> {
-    pub fn into_result<O: GetDiagRec>(self, odbc_object: &O) -> Result<T> {
+    pub fn into_result<O: GetDiagRec + HasWarnings>(self, odbc_object: &O) -> Result<T> {
         match self {
             Return::Success(value) => Ok(value),
             Return::SuccessWithInfo(value) => {
-                let mut i = 1;
-                while let Some(diag) = odbc_object.get_diag_rec(i) {
-                    warn!("{}", diag);
-                    i += 1;
-                }
+                collect_warnings(odbc_object);
                 Ok(value)
             }
-            Return::Error => {
-                // Return the first record
-                let diag = odbc_object.get_diag_rec(1).unwrap_or_else(DiagnosticRecord::empty);
-                error!("{}", diag);
-                let mut i = 2;
-                // log the rest
-                while let Some(diag) = odbc_object.get_diag_rec(i) {
-                    error!("{}", diag);
-                    i += 1;
-                }
-                Err(diag)
-            }
+            Return::Error(function) => Err(Error::from_diagnostics(function, odbc_object)),
         }
     }
 }
 
 
 // temporary glue code to odbc-safe
-pub fn try_into_option<T, E, D>(ret: safe::ReturnOption<T, E>, handle: &D) -> Result<Option<T>>
+pub fn try_into_option<T, E, D>(ret: safe::ReturnOption<T, E>, function: &'static str, handle: &D) -> Result<Option<T>>
 where
-    D: GetDiagRec,
+    D: GetDiagRec + HasWarnings,
 {
     match ret {
         safe::ReturnOption::Success(value) => Ok(Some(value)),
         safe::ReturnOption::Info(value) => {
-            let mut i = 1;
-            while let Some(diag) = handle.get_diag_rec(i) {
-                warn!("{}", diag);
-                i += 1;
-            }
+            collect_warnings(handle);
             Ok(Some(value))
         }
         safe::ReturnOption::NoData(_) => Ok(None),
-        safe::ReturnOption::Error(_) => {
-            // Return the first record
-            let diag = handle.get_diag_rec(1).unwrap_or_else(DiagnosticRecord::empty);
-            error!("{}", diag);
-            let mut i = 2;
-            // log the rest
-            while let Some(diag) = handle.get_diag_rec(i) {
-                error!("{}", diag);
-                i += 1;
-            }
-            Err(diag)
-        }
+        safe::ReturnOption::Error(_) => Err(Error::from_diagnostics(function, handle)),
     }
 }
 
 // temporary glue code to odbc-safe
-pub fn into_result<T, E>(ret: safe::Return<T, E>) -> Result<T>
+pub fn into_result_with<D, T, E>(diag: &D, function: &'static str, ret: safe::Return<T, E>) -> Result<T>
 where
-    T: GetDiagRec,
-    E: GetDiagRec,
+    D: GetDiagRec + HasWarnings,
 {
     match ret {
         safe::Return::Success(value) => Ok(value),
         safe::Return::Info(value) => {
-            let mut i = 1;
-            while let Some(diag) = value.get_diag_rec(i) {
-                warn!("{}", diag);
-                i += 1;
-            }
+            collect_warnings(diag);
             Ok(value)
         }
-        safe::Return::Error(value) => {
-            // Return the first record
-            let diag = value.get_diag_rec(1).unwrap_or_else(DiagnosticRecord::empty);
-            error!("{}", diag);
-            let mut i = 2;
-            // log the rest
-            while let Some(diag) = value.get_diag_rec(i) {
-                error!("{}", diag);
-                i += 1;
-            }
-            Err(diag)
-        }
+        safe::Return::Error(_) => Err(Error::from_diagnostics(function, diag)),
     }
 }
 
-// temporary glue code to odbc-safe
-pub fn into_result_with<D, T, E>(diag: &D, ret: safe::Return<T, E>) -> Result<T>
-where
-    D: GetDiagRec,
-{
-    match ret {
-        safe::Return::Success(value) => Ok(value),
-        safe::Return::Info(value) => {
-            let mut i = 1;
-            while let Some(diag_rec) = diag.get_diag_rec(i) {
-                warn!("{}", diag_rec);
-                i += 1;
-            }
-            Ok(value)
-        }
-        safe::Return::Error(_) => {
-            // Return the first record
-            let diag_rec = diag.get_diag_rec(1).unwrap_or_else(DiagnosticRecord::empty);
-            error!("{}", diag_rec);
-            let mut i = 2;
-            // log the rest
-            while let Some(diag_rec) = diag.get_diag_rec(i) {
-                error!("{}", diag_rec);
-                i += 1;
-            }
-            Err(diag_rec)
-        }
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display_all_records() {
+        let error = Error {
+            function: Some("SQLExecDirect"),
+            records: vec![
+                DiagnosticRecord::with_message(b"42000", "Syntax error"),
+                DiagnosticRecord::with_message(b"01000", "Statement not prepared"),
+            ],
+        };
+        assert_eq!(
+            error.to_string(),
+            "SQLExecDirect failed: State: 42000, Native error: 0, Message: Syntax error\n\
+             State: 01000, Native error: 0, Message: Statement not prepared"
+        );
+        assert_eq!(error.sql_state(), "42000");
+        let record: DiagnosticRecord = error.into();
+        assert_eq!(record.get_raw_state(), b"42000\0");
+    }
+
+    #[test]
+    fn crate_errors_have_no_function() {
+        let error: Error = DiagnosticRecord::with_message(b"07002", "Wrong parameter count").into();
+        assert_eq!(error.function(), None);
+        assert_eq!(error.records().len(), 1);
     }
 }
@@ -0,0 +1,257 @@
+//! Typed access to the SQLSTATE of diagnostic records
+use super::DiagnosticRecord;
+use std::fmt;
+use std::str;
+
+/// Five character SQLSTATE code, as defined by ODBC and the SQL standard
+///
+/// The first two characters denote the class of the condition (see `SqlStateClass`), the last
+/// three the subclass. Subclasses starting with a digit or one of `A` to `H` are defined by the
+/// standard, all others by the data source.
+///
+/// # Example
+/// ```
+/// # use odbc::*;
+/// let state = SqlState::from_bytes(b"40001").unwrap();
+/// assert_eq!(state.class(), SqlStateClass::TransactionRollback);
+/// assert!(state.is_retryable());
+/// assert_eq!(state, "40001");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SqlState([u8; 5]);
+
+/// Class of an `SqlState`, i.e. its first two characters
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SqlStateClass {
+    /// `00`
+    Success,
+    /// `01`
+    Warning,
+    /// `02`
+    NoData,
+    /// `07`, e.g. the wrong number of parameters has been bound
+    DynamicSqlError,
+    /// `08`, the connection could not be established or has been lost
+    ConnectionException,
+    /// `0A`
+    FeatureNotSupported,
+    /// `21`, e.g. a subquery returned more than one row
+    CardinalityViolation,
+    /// `22`, e.g. truncation, numeric overflow or division by zero
+    DataException,
+    /// `23`, e.g. unique, foreign key or not null constraint violations
+    IntegrityConstraintViolation,
+    /// `24`
+    InvalidCursorState,
+    /// `25`
+    InvalidTransactionState,
+    /// `28`
+    InvalidAuthorization,
+    /// `3D`
+    InvalidCatalogName,
+    /// `3F`
+    InvalidSchemaName,
+    /// `40`, e.g. serialization failures and deadlocks
+    TransactionRollback,
+    /// `42`, e.g. syntax errors, missing privileges and unknown tables or columns
+    SyntaxErrorOrAccessRuleViolation,
+    /// `44`
+    WithCheckOptionViolation,
+    /// `HY`, errors detected by the driver, e.g. timeouts and cancellation
+    DriverError,
+    /// `IM`, errors detected by the driver manager
+    DriverManagerError,
+    /// Any other class, e.g. one specific to the data source
+    Other,
+}
+
+impl SqlState {
+    /// Communication link failure, e.g. the server closed the connection
+    pub const COMMUNICATION_LINK_FAILURE: SqlState = SqlState(*b"08S01");
+    /// String or binary data has been truncated when returned to the application
+    pub const DATA_TRUNCATED: SqlState = SqlState(*b"01004");
+    /// Integrity constraint violation, e.g. a duplicate key
+    pub const INTEGRITY_CONSTRAINT_VIOLATION: SqlState = SqlState(*b"23000");
+    /// Serialization failure, which most data sources also report for deadlocks
+    pub const SERIALIZATION_FAILURE: SqlState = SqlState(*b"40001");
+    /// Syntax error or access violation
+    pub const SYNTAX_ERROR: SqlState = SqlState(*b"42000");
+    /// Timeout expired
+    pub const TIMEOUT_EXPIRED: SqlState = SqlState(*b"HYT00");
+    /// Operation canceled
+    pub const OPERATION_CANCELED: SqlState = SqlState(*b"HY008");
+
+    /// Creates a state from its five character code. `None` unless `code` consists of exactly
+    /// five ASCII letters or digits.
+    pub fn from_bytes(code: &[u8]) -> Option<SqlState> {
+        if code.len() != 5 || !code.iter().all(|b| b.is_ascii_alphanumeric()) {
+            return None;
+        }
+        let mut state = [0u8; 5];
+        state.copy_from_slice(code);
+        state.make_ascii_uppercase();
+        Some(SqlState(state))
+    }
+
+    /// The five character code, e.g. `"HY000"`
+    pub fn as_str(&self) -> &str {
+        // Only ASCII characters are accepted on construction
+        str::from_utf8(&self.0).unwrap()
+    }
+
+    /// The two character class code, e.g. `"HY"`
+    pub fn class_code(&self) -> &str {
+        &self.as_str()[..2]
+    }
+
+    /// The class of the condition
+    pub fn class(&self) -> SqlStateClass {
+        use self::SqlStateClass::*;
+        match &self.0[..2] {
+            b"00" => Success,
+            b"01" => Warning,
+            b"02" => NoData,
+            b"07" => DynamicSqlError,
+            b"08" => ConnectionException,
+            b"0A" => FeatureNotSupported,
+            b"21" => CardinalityViolation,
+            b"22" => DataException,
+            b"23" => IntegrityConstraintViolation,
+            b"24" => InvalidCursorState,
+            b"25" => InvalidTransactionState,
+            b"28" => InvalidAuthorization,
+            b"3D" => InvalidCatalogName,
+            b"3F" => InvalidSchemaName,
+            b"40" => TransactionRollback,
+            b"42" => SyntaxErrorOrAccessRuleViolation,
+            b"44" => WithCheckOptionViolation,
+            b"HY" => DriverError,
+            b"IM" => DriverManagerError,
+            _ => Other,
+        }
+    }
+
+    /// `true` for warnings (class `01`), which accompany `SQL_SUCCESS_WITH_INFO`
+    pub fn is_warning(&self) -> bool {
+        self.class() == SqlStateClass::Warning
+    }
+
+    /// `true` if the connection could not be established or has been lost (class `08`)
+    pub fn is_connection_failure(&self) -> bool {
+        self.class() == SqlStateClass::ConnectionException
+    }
+
+    /// `true` if an established connection is no longer usable, e.g. because the server went
+    /// away. The connection should be discarded.
+    pub fn is_connection_lost(&self) -> bool {
+        matches!(&self.0, b"08S01" | b"08003" | b"08006" | b"08007")
+    }
+
+    /// `true` for violations of unique, foreign key, check or not null constraints (class `23`)
+    pub fn is_integrity_constraint_violation(&self) -> bool {
+        self.class() == SqlStateClass::IntegrityConstraintViolation
+    }
+
+    /// `true` if the transaction has been rolled back because it could not be serialized with
+    /// concurrent transactions, or has been chosen as deadlock victim
+    pub fn is_serialization_failure(&self) -> bool {
+        // PostgreSQL reports deadlocks as `40P01`, most other data sources as `40001`
+        matches!(&self.0, b"40001" | b"40P01")
+    }
+
+    /// `true` if a query or login timeout expired
+    pub fn is_timeout(&self) -> bool {
+        matches!(&self.0, b"HYT00" | b"HYT01")
+    }
+
+    /// `true` if the operation has been canceled, e.g. using `SQLCancel`
+    pub fn is_cancellation(&self) -> bool {
+        // `57014` is reported by PostgreSQL and DB2
+        matches!(&self.0, b"HY008" | b"57014")
+    }
+
+    /// `true` for syntax errors, missing privileges and references to unknown tables or columns
+    /// (class `42`)
+    pub fn is_syntax_error(&self) -> bool {
+        self.class() == SqlStateClass::SyntaxErrorOrAccessRuleViolation
+    }
+
+    /// `true` if string or binary data has been truncated, either as warning while fetching data
+    /// or as error while inserting it
+    pub fn is_data_truncation(&self) -> bool {
+        matches!(&self.0, b"01004" | b"22001")
+    }
+
+    /// `true` for transient failures, which are likely to succeed if the operation is retried on
+    /// the same connection: serialization failures, deadlocks and expired query timeouts
+    /// (`HYT00`). Serialization failures roll back the transaction, so it needs to be retried as a
+    /// whole. Connection timeouts (`HYT01`) are not included, since the connection may no longer be
+    /// usable.
+    pub fn is_retryable(&self) -> bool {
+        self.is_serialization_failure() || *self == SqlState::TIMEOUT_EXPIRED
+    }
+}
+
+impl fmt::Display for SqlState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for SqlState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SqlState({})", self.as_str())
+    }
+}
+
+impl<'a> PartialEq<&'a str> for SqlState {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str().eq_ignore_ascii_case(other)
+    }
+}
+
+impl DiagnosticRecord {
+    /// The SQLSTATE of the record. Falls back to `HY000` (general error) if the driver did not
+    /// report a valid state.
+    pub fn sql_state(&self) -> SqlState {
+        SqlState::from_bytes(&self.get_raw_state()[..5]).unwrap_or(SqlState(*b"HY000"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn classes() {
+        let state = |code: &str| SqlState::from_bytes(code.as_bytes()).unwrap();
+        assert_eq!(state("08S01").class(), SqlStateClass::ConnectionException);
+        assert_eq!(state("23505").class(), SqlStateClass::IntegrityConstraintViolation);
+        assert_eq!(state("0A000").class(), SqlStateClass::FeatureNotSupported);
+        assert_eq!(state("HYT00").class(), SqlStateClass::DriverError);
+        assert_eq!(state("57014").class(), SqlStateClass::Other);
+        assert_eq!(state("hyt00"), SqlState::TIMEOUT_EXPIRED);
+    }
+
+    #[test]
+    fn predicates() {
+        assert!(SqlState::COMMUNICATION_LINK_FAILURE.is_connection_lost());
+        assert!(!SqlState::from_bytes(b"08001").unwrap().is_connection_lost());
+        assert!(SqlState::from_bytes(b"08001").unwrap().is_connection_failure());
+        assert!(SqlState::from_bytes(b"40P01").unwrap().is_retryable());
+        assert!(SqlState::TIMEOUT_EXPIRED.is_retryable());
+        assert!(!SqlState::from_bytes(b"HYT01").unwrap().is_retryable());
+        assert!(!SqlState::INTEGRITY_CONSTRAINT_VIOLATION.is_retryable());
+        assert!(SqlState::DATA_TRUNCATED.is_warning());
+        assert!(SqlState::DATA_TRUNCATED.is_data_truncation());
+        assert!(SqlState::from_bytes(b"42S02").unwrap().is_syntax_error());
+        assert!(SqlState::OPERATION_CANCELED.is_cancellation());
+    }
+
+    #[test]
+    fn invalid_codes() {
+        assert_eq!(SqlState::from_bytes(b"HY00"), None);
+        assert_eq!(SqlState::from_bytes(b"HY00\0"), None);
+        assert_eq!(DiagnosticRecord::empty().sql_state(), "HY000");
+    }
+}
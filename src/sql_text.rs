@@ -0,0 +1,27 @@
+//! Strings passed to the driver, encoded for either the narrow or the wide variant of an ODBC
+//! function
+use encoding_rs::Encoding;
+
+pub enum SqlText {
+    Narrow(Vec<u8>),
+    Wide(Vec<u16>),
+}
+
+impl SqlText {
+    /// Encodes `text` as UTF-16 if `wide` is set, using `encoding` otherwise
+    pub fn new(text: &str, encoding: &'static Encoding, wide: bool) -> SqlText {
+        if wide {
+            SqlText::Wide(text.encode_utf16().collect())
+        } else {
+            SqlText::Narrow(encoding.encode(text).0.into_owned())
+        }
+    }
+
+    /// Length in code units, which is what ODBC functions expect as text length
+    pub fn len(&self) -> usize {
+        match *self {
+            SqlText::Narrow(ref bytes) => bytes.len(),
+            SqlText::Wide(ref units) => units.len(),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Cache of prepared statements, allowing a connection to reuse statement handles
+use super::polling::SQL_ASYNC_ENABLE_OFF;
+use super::{NoResult, Prepared, Statement};
+use odbc_safe::AutocommitMode;
+use std::collections::VecDeque;
+use std::fmt;
+use std::mem;
+use std::sync::{Mutex, MutexGuard};
+use warnings::{HasWarnings, Warnings};
+use {ffi, Connection, HasEncodings, Raii, Result};
+
+/// Prepared statement handles of a connection, keyed by their SQL text. Evicts the least recently
+/// used handle once more than `capacity` handles are kept.
+pub(crate) struct StatementCache {
+    capacity: usize,
+    // Least recently used first
+    entries: VecDeque<(String, CachedHandle)>,
+}
+
+/// Statement handle owned by the cache, freed if evicted
+struct CachedHandle(ffi::SQLHSTMT);
+
+// Statement handles may be used from any thread, as long as they are not used concurrently.
+unsafe impl Send for CachedHandle {}
+
+impl CachedHandle {
+    fn into_raw(self) -> ffi::SQLHSTMT {
+        let handle = self.0;
+        mem::forget(self);
+        handle
+    }
+}
+
+impl Drop for CachedHandle {
+    fn drop(&mut self) {
+        if unsafe { ffi::SQLFreeHandle(ffi::SQL_HANDLE_STMT, self.0 as ffi::SQLHANDLE) }
+            != ffi::SQL_SUCCESS
+        {
+            error!("Error freeing cached statement handle");
+        }
+    }
+}
+
+impl fmt::Debug for StatementCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StatementCache")
+            .field("capacity", &self.capacity)
+            .field("len", &self.entries.len())
+            .finish()
+    }
+}
+
+impl StatementCache {
+    /// An empty cache with capacity zero, i.e. caching is disabled
+    pub(crate) fn new() -> StatementCache {
+        StatementCache {
+            capacity: 0,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Frees all handles kept by the cache
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Removes a handle prepared with `sql` from the cache
+    fn take(&mut self, sql: &str) -> Option<ffi::SQLHSTMT> {
+        let position = self.entries.iter().rposition(|(text, _)| text == sql)?;
+        self.entries.remove(position).map(|(_, handle)| handle.into_raw())
+    }
+
+    /// Called instead of freeing a statement handle checked out with `prepare_cached`. Closes its
+    /// cursor, resets its parameters and keeps it for reuse. Returns `false` if the handle has not
+    /// been kept and still needs to be freed.
+    ///
+    /// Unbinding columns and resetting parameters drops all records of the application
+    /// descriptors, including the fields set for numeric and interval values. Other statement
+    /// attributes are kept, apart from asynchronous mode.
+    pub(crate) fn recycle(cache: &Mutex<StatementCache>, sql: String, handle: ffi::SQLHSTMT) -> bool {
+        let mut cache = lock(cache);
+        if cache.capacity == 0 {
+            return false;
+        }
+        for &option in &[ffi::SQL_CLOSE, ffi::SQL_UNBIND, ffi::SQL_RESET_PARAMS] {
+            match unsafe { ffi::SQLFreeStmt(handle, option) } {
+                ffi::SQL_SUCCESS | ffi::SQL_SUCCESS_WITH_INFO => (),
+                _ => return false,
+            }
+        }
+        // Only left enabled by an asynchronous operation which failed. Drivers without support for
+        // asynchronous mode may fail to disable it, which is fine.
+        let _ = unsafe {
+            ffi::SQLSetStmtAttr(
+                handle,
+                ffi::SqlStatementAttribute::SQL_ATTR_ASYNC_ENABLE,
+                SQL_ASYNC_ENABLE_OFF as ffi::SQLPOINTER,
+                0,
+            )
+        };
+        cache.entries.push_back((sql, CachedHandle(handle)));
+        cache.evict();
+        true
+    }
+}
+
+fn lock(cache: &Mutex<StatementCache>) -> MutexGuard<'_, StatementCache> {
+    // The cache is consistent between statements, so it is fine to carry on after a panic
+    cache.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl<'env, AC: AutocommitMode> Connection<'env, AC> {
+    /// Enables caching of statements prepared with `prepare_cached`, keeping up to `capacity`
+    /// prepared statement handles for reuse. The least recently used handles are freed once the
+    /// capacity is exceeded. A capacity of zero, the default, disables the cache and frees all
+    /// cached handles.
+    pub fn set_statement_cache_capacity(&self, capacity: usize) {
+        lock(self.statement_cache()).set_capacity(capacity);
+    }
+
+    /// Number of prepared statement handles currently kept in the cache
+    pub fn cached_statement_count(&self) -> usize {
+        lock(self.statement_cache()).entries.len()
+    }
+
+    /// Prepares `sql_text` like `Statement::prepare`, but reuses a statement handle prepared with
+    /// the same text earlier, if the statement cache holds one.
+    ///
+    /// Once dropped, the returned statement is handed back to the cache rather than being freed.
+    /// Its cursor is closed and its parameters are reset, so it can be used again right away. If
+    /// the cache is disabled (see `set_statement_cache_capacity`) this behaves like preparing a
+    /// new statement.
+    ///
+    /// Statement attributes set through the raw handle, e.g. query timeout, maximum number of rows
+    /// or cursor type, are not reset. They still apply the next time the statement is taken from
+    /// the cache, so either set them every time or do not cache such statements.
+    ///
+    /// # Example
+    /// ```
+    /// # use odbc::*;
+    /// # fn doc() -> Result<()> {
+    /// let env = create_environment_v3().map_err(|e| e.unwrap())?;
+    /// let conn = env.connect("TestDataSource", "", "")?;
+    /// conn.set_statement_cache_capacity(20);
+    /// for year in &[1968, 1993] {
+    ///     let stmt = conn.prepare_cached("SELECT TITLE FROM MOVIES WHERE YEAR = ?")?;
+    ///     let stmt = stmt.bind_parameter(1, year)?;
+    ///     if let Data(mut stmt) = stmt.execute()? {
+    ///         // ...
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prepare_cached<'a, 'b>(
+        &'a self,
+        sql_text: &str,
+    ) -> Result<Statement<'a, 'b, Prepared, NoResult, AC>> {
+        let cached = lock(self.statement_cache()).take(sql_text);
+        let mut stmt = match cached {
+            Some(handle) => {
+                let handler = self.warnings().and_then(Warnings::handler);
+                Statement::with_raii(unsafe { Raii::from_raw(handle, self.encodings(), handler) })
+            }
+            None => Statement::with_parent(self)?.prepare(sql_text)?,
+        };
+        stmt.raii.set_cache(self.statement_cache(), sql_text.to_owned());
+        Ok(stmt)
+    }
+}
@@ -0,0 +1,276 @@
+//! Column attributes beyond those of `ColumnDescriptor`, retrieved using `SQLColAttribute`
+use super::{HasResult, NoResult, Prepared, Statement};
+use odbc_safe::AutocommitMode;
+use std::cmp;
+use std::ptr::null_mut;
+use {ffi, Handle, HasEncodings, Raii, Result, Return};
+
+/// Field of a result set column, which can be retrieved with `Statement::col_attribute`
+///
+/// Each variant corresponds to the `SQL_DESC_*` field identifier of the same name.
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColumnAttribute {
+    /// Column label or title, e.g. the alias given by `AS`. Text.
+    Label = 18,
+    /// Column name, or the label if the column is unnamed. Text.
+    Name = 1011,
+    /// Name of the column in the underlying table. Text.
+    BaseColumnName = 22,
+    /// Name of the underlying table. Text.
+    BaseTableName = 23,
+    /// Name of the table containing the column. Text.
+    TableName = 15,
+    /// Schema of the table containing the column. Text.
+    SchemaName = 16,
+    /// Catalog of the table containing the column. Text.
+    CatalogName = 17,
+    /// Data source dependent type name, e.g. `VARCHAR` or `MONEY`. Text.
+    TypeName = 14,
+    /// Localized type name. Text.
+    LocalTypeName = 29,
+    /// Concise SQL data type, including interval types. Numeric.
+    ConciseType = 2,
+    /// Maximum number of characters required to display the data. Numeric.
+    DisplaySize = 6,
+    /// Maximum length of character or binary data in bytes. Numeric.
+    OctetLength = 1013,
+    /// Maximum length of character data in characters. Numeric.
+    Length = 1003,
+    /// Precision of numeric types and of fractional seconds. Numeric.
+    Precision = 1005,
+    /// Scale of numeric types. Numeric.
+    Scale = 1006,
+    /// `SQL_NO_NULLS`, `SQL_NULLABLE` or `SQL_NULLABLE_UNKNOWN`. Numeric.
+    Nullable = 1008,
+    /// `1` if the type is unsigned or not numeric. Numeric.
+    Unsigned = 8,
+    /// `1` if the column is auto-incrementing. Numeric.
+    AutoUniqueValue = 11,
+    /// `1` if the column is treated as case sensitive in comparisons. Numeric.
+    CaseSensitive = 12,
+    /// `1` if the type has a fixed precision and scale, e.g. money. Numeric.
+    FixedPrecScale = 9,
+    /// How the column can be used in a `WHERE` clause, see `Searchable`. Numeric.
+    Searchable = 13,
+    /// Whether the column can be updated, see `Updatable`. Numeric.
+    Updatable = 10,
+}
+
+impl ColumnAttribute {
+    /// `true` for attributes with a character value
+    pub fn is_text(self) -> bool {
+        use self::ColumnAttribute::*;
+        matches!(
+            self,
+            Label | Name | BaseColumnName | BaseTableName | TableName | SchemaName | CatalogName
+                | TypeName | LocalTypeName
+        )
+    }
+}
+
+/// Value of a `ColumnAttribute`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttributeValue {
+    Text(String),
+    Numeric(ffi::SQLLEN),
+}
+
+impl AttributeValue {
+    /// The value of a text attribute
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            AttributeValue::Text(ref text) => Some(text),
+            AttributeValue::Numeric(_) => None,
+        }
+    }
+
+    /// The value of a numeric attribute
+    pub fn as_numeric(&self) -> Option<ffi::SQLLEN> {
+        match *self {
+            AttributeValue::Text(_) => None,
+            AttributeValue::Numeric(value) => Some(value),
+        }
+    }
+}
+
+/// How a column can be used in a `WHERE` clause
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Searchable {
+    /// The column can not be used in a `WHERE` clause
+    None,
+    /// Only with `LIKE`
+    Char,
+    /// With all comparison operators except `LIKE`
+    Basic,
+    /// With any comparison operator
+    Searchable,
+}
+
+/// Whether a column can be updated
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Updatable {
+    ReadOnly,
+    Write,
+    Unknown,
+}
+
+/// Extended description of a result set column. Attributes the driver does not know are empty,
+/// zero, `false`, `Searchable::None` or `Updatable::Unknown` respectively.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ColumnAttributes {
+    pub label: String,
+    pub base_table_name: String,
+    pub base_column_name: String,
+    pub table_name: String,
+    pub catalog_name: String,
+    pub schema_name: String,
+    pub type_name: String,
+    pub display_size: ffi::SQLLEN,
+    pub octet_length: ffi::SQLLEN,
+    pub unsigned: bool,
+    pub auto_increment: bool,
+    pub case_sensitive: bool,
+    pub searchable: Searchable,
+    pub updatable: Updatable,
+}
+
+impl<'a, 'b, S, AC: AutocommitMode> Statement<'a, 'b, S, HasResult, AC> {
+    /// Returns a single attribute of the result set column with a given index. Note: indexing is
+    /// starting from 1.
+    pub fn col_attribute(&self, idx: u16, attribute: ColumnAttribute) -> Result<AttributeValue> {
+        self.raii.col_attribute(idx, attribute).into_result(self)
+    }
+
+    /// Returns the extended description of the result set column with a given index
+    pub fn col_attributes(&self, idx: u16) -> Result<ColumnAttributes> {
+        self.raii.col_attributes(idx)
+    }
+}
+
+impl<'a, 'b, AC: AutocommitMode> Statement<'a, 'b, Prepared, NoResult, AC> {
+    /// Returns a single attribute of the result set column with a given index. Note: indexing is
+    /// starting from 1.
+    pub fn col_attribute(&self, idx: u16, attribute: ColumnAttribute) -> Result<AttributeValue> {
+        self.raii.col_attribute(idx, attribute).into_result(self)
+    }
+
+    /// Returns the extended description of the result set column with a given index
+    pub fn col_attributes(&self, idx: u16) -> Result<ColumnAttributes> {
+        self.raii.col_attributes(idx)
+    }
+}
+
+impl<'p> Raii<'p, ffi::Stmt> {
+    fn col_attributes(&self, idx: u16) -> Result<ColumnAttributes> {
+        // Drivers report attributes they do not know as invalid (`HY091`) or not implemented
+        // (`HYC00`). Those are left at their default, anything else is an error.
+        let get = |attribute| -> Result<Option<AttributeValue>> {
+            match self.col_attribute(idx, attribute).into_result(self) {
+                Ok(value) => Ok(Some(value)),
+                Err(ref error) if error.sql_state() == "HY091" || error.sql_state() == "HYC00" => {
+                    Ok(None)
+                }
+                Err(error) => Err(error),
+            }
+        };
+        let text = |attribute| -> Result<String> {
+            let value = get(attribute)?;
+            Ok(value.as_ref().and_then(AttributeValue::as_str).unwrap_or_default().to_owned())
+        };
+        let numeric = |attribute| -> Result<ffi::SQLLEN> {
+            let value = get(attribute)?;
+            Ok(value.as_ref().and_then(AttributeValue::as_numeric).unwrap_or_default())
+        };
+        Ok(ColumnAttributes {
+            label: text(ColumnAttribute::Label)?,
+            base_table_name: text(ColumnAttribute::BaseTableName)?,
+            base_column_name: text(ColumnAttribute::BaseColumnName)?,
+            table_name: text(ColumnAttribute::TableName)?,
+            catalog_name: text(ColumnAttribute::CatalogName)?,
+            schema_name: text(ColumnAttribute::SchemaName)?,
+            type_name: text(ColumnAttribute::TypeName)?,
+            display_size: numeric(ColumnAttribute::DisplaySize)?,
+            octet_length: numeric(ColumnAttribute::OctetLength)?,
+            unsigned: numeric(ColumnAttribute::Unsigned)? != 0,
+            auto_increment: numeric(ColumnAttribute::AutoUniqueValue)? != 0,
+            case_sensitive: numeric(ColumnAttribute::CaseSensitive)? != 0,
+            searchable: match numeric(ColumnAttribute::Searchable)? {
+                0 => Searchable::None,
+                1 => Searchable::Char,
+                2 => Searchable::Basic,
+                _ => Searchable::Searchable,
+            },
+            updatable: match get(ColumnAttribute::Updatable)?.and_then(|value| value.as_numeric()) {
+                Some(0) => Updatable::ReadOnly,
+                Some(1) => Updatable::Write,
+                _ => Updatable::Unknown,
+            },
+        })
+    }
+
+    fn col_attribute(&self, idx: u16, attribute: ColumnAttribute) -> Return<AttributeValue> {
+        if !attribute.is_text() {
+            let mut value: ffi::SQLLEN = 0;
+            return match unsafe {
+                ffi::SQLColAttributeW(
+                    self.handle(),
+                    idx,
+                    attribute as ffi::SQLUSMALLINT,
+                    null_mut(),
+                    0,
+                    null_mut(),
+                    &mut value as *mut ffi::SQLLEN,
+                )
+            } {
+                ffi::SQL_SUCCESS => Return::Success(AttributeValue::Numeric(value)),
+                ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(AttributeValue::Numeric(value)),
+                ffi::SQL_ERROR => Return::Error("SQLColAttribute"),
+                r => panic!("SQLColAttribute returned unexpected result: {:?}", r),
+            };
+        }
+        let wide = self.encodings().wide;
+        let mut buffer: Vec<u8> = vec![0; 512];
+        loop {
+            let mut string_length: ffi::SQLSMALLINT = 0;
+            let ret = unsafe {
+                let col_attribute = if wide { ffi::SQLColAttributeW } else { ffi::SQLColAttribute };
+                col_attribute(
+                    self.handle(),
+                    idx,
+                    attribute as ffi::SQLUSMALLINT,
+                    buffer.as_mut_ptr() as ffi::SQLPOINTER,
+                    buffer.len() as ffi::SQLSMALLINT,
+                    &mut string_length as *mut ffi::SQLSMALLINT,
+                    null_mut(),
+                )
+            };
+            // Length in bytes, excluding the terminating zero
+            let length = cmp::max(string_length, 0) as usize;
+            let terminator = if wide { 2 } else { 1 };
+            if ret == ffi::SQL_SUCCESS_WITH_INFO && length + terminator > buffer.len() {
+                // Truncated, retry with a buffer large enough for the whole value
+                buffer.resize(length + terminator, 0);
+                continue;
+            }
+            let length = cmp::min(length, buffer.len() - terminator);
+            let text = || {
+                if wide {
+                    let units: Vec<u16> = buffer[..length]
+                        .chunks(2)
+                        .map(|c| u16::from_ne_bytes([c[0], c.get(1).cloned().unwrap_or(0)]))
+                        .collect();
+                    AttributeValue::Text(String::from_utf16_lossy(&units))
+                } else {
+                    AttributeValue::Text(self.encodings().db.decode(&buffer[..length]).0.into_owned())
+                }
+            };
+            return match ret {
+                ffi::SQL_SUCCESS => Return::Success(text()),
+                ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(text()),
+                ffi::SQL_ERROR => Return::Error("SQLColAttribute"),
+                r => panic!("SQLColAttribute returned unexpected result: {:?}", r),
+            };
+        }
+    }
+}
@@ -0,0 +1,548 @@
+//! Date and time types of the `chrono` and `time` crates, exchanged as `SqlDate`, `SqlTime` and
+//! `SqlTimestamp`
+//!
+//! Values are converted into the ODBC structs when bound, so they are bound as encoded values.
+//! Timestamps keep their fractional seconds up to nanoseconds. Times are truncated to seconds,
+//! since `SQL_TIME_STRUCT` has no fractional part.
+//!
+//! Binding a date with a year outside of `i16` fails with `22008` (datetime field overflow),
+//! fetching a value the driver reports as an invalid date or time with `22007` (invalid datetime
+//! format). Only `OdbcType::convert` and `OdbcType::encoded_value` panic in these cases.
+use super::types::{fraction_digits, timestamp_column_size, EncodedValue, OdbcType};
+use super::{SqlDate, SqlTime, SqlTimestamp};
+use encoding_rs::{Encoding, UTF_8};
+use std::mem::size_of;
+use std::slice::from_raw_parts;
+use {ffi, DecodePolicy, DiagnosticRecord, Error};
+
+/// Bytes of one of the ODBC date and time structs
+fn bytes_of<T: Copy>(value: &T) -> Vec<u8> {
+    unsafe { from_raw_parts(value as *const T as *const u8, size_of::<T>()) }.to_vec()
+}
+
+fn encode_date(date: &SqlDate) -> EncodedValue {
+    EncodedValue::new(Some(bytes_of(date)))
+}
+
+fn encode_time(time: &SqlTime) -> EncodedValue {
+    EncodedValue::new(Some(bytes_of(time)))
+}
+
+fn encode_timestamp(timestamp: &SqlTimestamp) -> EncodedValue {
+    EncodedValue::with_column_size(bytes_of(timestamp), timestamp_column_size(timestamp.fraction))
+}
+
+fn sql_year(year: i32) -> ::Result<i16> {
+    if year < i16::MIN as i32 || year > i16::MAX as i32 {
+        let message = format!("Year {} is out of the range of SQL_DATE_STRUCT", year);
+        return Err(DiagnosticRecord::with_message(b"22008", &message).into());
+    }
+    Ok(year as i16)
+}
+
+fn invalid(what: &str) -> Error {
+    DiagnosticRecord::with_message(b"22007", &format!("Driver returned an invalid {}", what)).into()
+}
+
+/// `OdbcType::convert` can not report errors
+fn expect_valid<T>(value: ::Result<T>) -> T {
+    value.unwrap_or_else(|error| panic!("{}", error))
+}
+
+// Leap seconds are represented by nanoseconds of a second exceeding one second
+fn sql_fraction(nanos: u32) -> u32 {
+    nanos.min(999_999_999)
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_types {
+    use super::*;
+    use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+
+    fn to_sql_date(date: &NaiveDate) -> ::Result<SqlDate> {
+        Ok(SqlDate {
+            year: sql_year(date.year())?,
+            month: date.month() as u16,
+            day: date.day() as u16,
+        })
+    }
+
+    fn to_sql_time(time: &NaiveTime) -> SqlTime {
+        SqlTime {
+            hour: time.hour() as u16,
+            minute: time.minute() as u16,
+            second: time.second() as u16,
+        }
+    }
+
+    fn to_sql_timestamp(datetime: &NaiveDateTime) -> ::Result<SqlTimestamp> {
+        Ok(SqlTimestamp {
+            year: sql_year(datetime.year())?,
+            month: datetime.month() as u16,
+            day: datetime.day() as u16,
+            hour: datetime.hour() as u16,
+            minute: datetime.minute() as u16,
+            second: datetime.second() as u16,
+            fraction: sql_fraction(datetime.nanosecond()),
+        })
+    }
+
+    fn from_sql_date(date: &SqlDate) -> ::Result<NaiveDate> {
+        NaiveDate::from_ymd_opt(date.year as i32, date.month as u32, date.day as u32)
+            .ok_or_else(|| invalid("date"))
+    }
+
+    fn from_sql_time(time: &SqlTime) -> ::Result<NaiveTime> {
+        NaiveTime::from_hms_opt(time.hour as u32, time.minute as u32, time.second as u32)
+            .ok_or_else(|| invalid("time"))
+    }
+
+    fn from_sql_timestamp(timestamp: &SqlTimestamp) -> ::Result<NaiveDateTime> {
+        let date = NaiveDate::from_ymd_opt(timestamp.year as i32, timestamp.month as u32, timestamp.day as u32);
+        let time = NaiveTime::from_hms_nano_opt(
+            timestamp.hour as u32,
+            timestamp.minute as u32,
+            timestamp.second as u32,
+            timestamp.fraction,
+        );
+        match (date, time) {
+            (Some(date), Some(time)) => Ok(date.and_time(time)),
+            _ => Err(invalid("timestamp")),
+        }
+    }
+
+    unsafe impl<'a> OdbcType<'a> for NaiveDate {
+        fn sql_data_type() -> ffi::SqlDataType {
+            SqlDate::sql_data_type()
+        }
+        fn c_data_type() -> ffi::SqlCDataType {
+            SqlDate::c_data_type()
+        }
+
+        fn convert(buffer: &'a [u8]) -> Self {
+            expect_valid(Self::convert_with_encoding(buffer, UTF_8, DecodePolicy::Lossy))
+        }
+
+        fn column_size(&self) -> ffi::SQLULEN {
+            size_of::<SqlDate>() as ffi::SQLULEN
+        }
+        fn value_ptr(&self) -> ffi::SQLPOINTER {
+            self as *const Self as ffi::SQLPOINTER
+        }
+
+        fn encoded_value(&self) -> EncodedValue {
+            expect_valid(self.encoded_value_with_encoding(UTF_8))
+        }
+
+        fn convert_with_encoding(
+            buffer: &'a [u8],
+            encoding: &'static Encoding,
+            policy: DecodePolicy,
+        ) -> ::Result<Self> {
+            from_sql_date(&SqlDate::convert_with_encoding(buffer, encoding, policy)?)
+        }
+
+        fn encoded_value_with_encoding(
+            &self,
+            _encoding: &'static Encoding,
+        ) -> ::Result<EncodedValue> {
+            to_sql_date(self).map(|date| encode_date(&date))
+        }
+    }
+
+    unsafe impl<'a> OdbcType<'a> for NaiveTime {
+        fn sql_data_type() -> ffi::SqlDataType {
+            SqlTime::sql_data_type()
+        }
+        fn c_data_type() -> ffi::SqlCDataType {
+            SqlTime::c_data_type()
+        }
+
+        fn convert(buffer: &'a [u8]) -> Self {
+            expect_valid(Self::convert_with_encoding(buffer, UTF_8, DecodePolicy::Lossy))
+        }
+
+        fn column_size(&self) -> ffi::SQLULEN {
+            size_of::<SqlTime>() as ffi::SQLULEN
+        }
+        fn value_ptr(&self) -> ffi::SQLPOINTER {
+            self as *const Self as ffi::SQLPOINTER
+        }
+
+        fn encoded_value(&self) -> EncodedValue {
+            encode_time(&to_sql_time(self))
+        }
+
+        fn convert_with_encoding(
+            buffer: &'a [u8],
+            encoding: &'static Encoding,
+            policy: DecodePolicy,
+        ) -> ::Result<Self> {
+            from_sql_time(&SqlTime::convert_with_encoding(buffer, encoding, policy)?)
+        }
+    }
+
+    unsafe impl<'a> OdbcType<'a> for NaiveDateTime {
+        fn sql_data_type() -> ffi::SqlDataType {
+            SqlTimestamp::sql_data_type()
+        }
+        fn c_data_type() -> ffi::SqlCDataType {
+            SqlTimestamp::c_data_type()
+        }
+
+        fn convert(buffer: &'a [u8]) -> Self {
+            expect_valid(Self::convert_with_encoding(buffer, UTF_8, DecodePolicy::Lossy))
+        }
+
+        fn column_size(&self) -> ffi::SQLULEN {
+            timestamp_column_size(sql_fraction(self.nanosecond()))
+        }
+        fn decimal_digits(&self) -> ffi::SQLSMALLINT {
+            fraction_digits(sql_fraction(self.nanosecond()))
+        }
+        fn value_ptr(&self) -> ffi::SQLPOINTER {
+            self as *const Self as ffi::SQLPOINTER
+        }
+
+        fn encoded_value(&self) -> EncodedValue {
+            expect_valid(self.encoded_value_with_encoding(UTF_8))
+        }
+
+        fn convert_with_encoding(
+            buffer: &'a [u8],
+            encoding: &'static Encoding,
+            policy: DecodePolicy,
+        ) -> ::Result<Self> {
+            from_sql_timestamp(&SqlTimestamp::convert_with_encoding(buffer, encoding, policy)?)
+        }
+
+        fn encoded_value_with_encoding(
+            &self,
+            _encoding: &'static Encoding,
+        ) -> ::Result<EncodedValue> {
+            to_sql_timestamp(self).map(|timestamp| encode_timestamp(&timestamp))
+        }
+    }
+
+    /// Exchanged as timestamp without time zone, which is assumed to be UTC
+    unsafe impl<'a> OdbcType<'a> for DateTime<Utc> {
+        fn sql_data_type() -> ffi::SqlDataType {
+            SqlTimestamp::sql_data_type()
+        }
+        fn c_data_type() -> ffi::SqlCDataType {
+            SqlTimestamp::c_data_type()
+        }
+
+        #[allow(deprecated)]
+        fn convert(buffer: &'a [u8]) -> Self {
+            DateTime::from_naive_utc_and_offset(NaiveDateTime::convert(buffer), Utc)
+        }
+
+        fn column_size(&self) -> ffi::SQLULEN {
+            self.naive_utc().column_size()
+        }
+        fn decimal_digits(&self) -> ffi::SQLSMALLINT {
+            self.naive_utc().decimal_digits()
+        }
+        fn value_ptr(&self) -> ffi::SQLPOINTER {
+            self as *const Self as ffi::SQLPOINTER
+        }
+
+        fn encoded_value(&self) -> EncodedValue {
+            self.naive_utc().encoded_value()
+        }
+
+        fn convert_with_encoding(
+            buffer: &'a [u8],
+            encoding: &'static Encoding,
+            policy: DecodePolicy,
+        ) -> ::Result<Self> {
+            NaiveDateTime::convert_with_encoding(buffer, encoding, policy)
+                .map(|naive| DateTime::from_naive_utc_and_offset(naive, Utc))
+        }
+
+        fn encoded_value_with_encoding(
+            &self,
+            encoding: &'static Encoding,
+        ) -> ::Result<EncodedValue> {
+            self.naive_utc().encoded_value_with_encoding(encoding)
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+mod time_types {
+    use super::*;
+    use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+    use std::convert::TryFrom;
+
+    fn to_sql_date(date: &Date) -> ::Result<SqlDate> {
+        Ok(SqlDate {
+            year: sql_year(date.year())?,
+            month: u8::from(date.month()) as u16,
+            day: date.day() as u16,
+        })
+    }
+
+    fn to_sql_time(time: &Time) -> SqlTime {
+        SqlTime {
+            hour: time.hour() as u16,
+            minute: time.minute() as u16,
+            second: time.second() as u16,
+        }
+    }
+
+    fn to_sql_timestamp(datetime: &PrimitiveDateTime) -> ::Result<SqlTimestamp> {
+        Ok(SqlTimestamp {
+            year: sql_year(datetime.year())?,
+            month: u8::from(datetime.month()) as u16,
+            day: datetime.day() as u16,
+            hour: datetime.hour() as u16,
+            minute: datetime.minute() as u16,
+            second: datetime.second() as u16,
+            fraction: datetime.nanosecond(),
+        })
+    }
+
+    fn from_sql_date(date: &SqlDate) -> ::Result<Date> {
+        Month::try_from(date.month as u8)
+            .and_then(|month| Date::from_calendar_date(date.year as i32, month, date.day as u8))
+            .map_err(|_| invalid("date"))
+    }
+
+    fn from_sql_time(time: &SqlTime) -> ::Result<Time> {
+        Time::from_hms(time.hour as u8, time.minute as u8, time.second as u8)
+            .map_err(|_| invalid("time"))
+    }
+
+    fn from_sql_timestamp(timestamp: &SqlTimestamp) -> ::Result<PrimitiveDateTime> {
+        let date = Month::try_from(timestamp.month as u8).and_then(|month| {
+            Date::from_calendar_date(timestamp.year as i32, month, timestamp.day as u8)
+        });
+        let time = Time::from_hms_nano(
+            timestamp.hour as u8,
+            timestamp.minute as u8,
+            timestamp.second as u8,
+            timestamp.fraction,
+        );
+        match (date, time) {
+            (Ok(date), Ok(time)) => Ok(PrimitiveDateTime::new(date, time)),
+            _ => Err(invalid("timestamp")),
+        }
+    }
+
+    unsafe impl<'a> OdbcType<'a> for Date {
+        fn sql_data_type() -> ffi::SqlDataType {
+            SqlDate::sql_data_type()
+        }
+        fn c_data_type() -> ffi::SqlCDataType {
+            SqlDate::c_data_type()
+        }
+
+        fn convert(buffer: &'a [u8]) -> Self {
+            expect_valid(Self::convert_with_encoding(buffer, UTF_8, DecodePolicy::Lossy))
+        }
+
+        fn column_size(&self) -> ffi::SQLULEN {
+            size_of::<SqlDate>() as ffi::SQLULEN
+        }
+        fn value_ptr(&self) -> ffi::SQLPOINTER {
+            self as *const Self as ffi::SQLPOINTER
+        }
+
+        fn encoded_value(&self) -> EncodedValue {
+            expect_valid(self.encoded_value_with_encoding(UTF_8))
+        }
+
+        fn convert_with_encoding(
+            buffer: &'a [u8],
+            encoding: &'static Encoding,
+            policy: DecodePolicy,
+        ) -> ::Result<Self> {
+            from_sql_date(&SqlDate::convert_with_encoding(buffer, encoding, policy)?)
+        }
+
+        fn encoded_value_with_encoding(
+            &self,
+            _encoding: &'static Encoding,
+        ) -> ::Result<EncodedValue> {
+            to_sql_date(self).map(|date| encode_date(&date))
+        }
+    }
+
+    unsafe impl<'a> OdbcType<'a> for Time {
+        fn sql_data_type() -> ffi::SqlDataType {
+            SqlTime::sql_data_type()
+        }
+        fn c_data_type() -> ffi::SqlCDataType {
+            SqlTime::c_data_type()
+        }
+
+        fn convert(buffer: &'a [u8]) -> Self {
+            expect_valid(Self::convert_with_encoding(buffer, UTF_8, DecodePolicy::Lossy))
+        }
+
+        fn column_size(&self) -> ffi::SQLULEN {
+            size_of::<SqlTime>() as ffi::SQLULEN
+        }
+        fn value_ptr(&self) -> ffi::SQLPOINTER {
+            self as *const Self as ffi::SQLPOINTER
+        }
+
+        fn encoded_value(&self) -> EncodedValue {
+            encode_time(&to_sql_time(self))
+        }
+
+        fn convert_with_encoding(
+            buffer: &'a [u8],
+            encoding: &'static Encoding,
+            policy: DecodePolicy,
+        ) -> ::Result<Self> {
+            from_sql_time(&SqlTime::convert_with_encoding(buffer, encoding, policy)?)
+        }
+    }
+
+    unsafe impl<'a> OdbcType<'a> for PrimitiveDateTime {
+        fn sql_data_type() -> ffi::SqlDataType {
+            SqlTimestamp::sql_data_type()
+        }
+        fn c_data_type() -> ffi::SqlCDataType {
+            SqlTimestamp::c_data_type()
+        }
+
+        fn convert(buffer: &'a [u8]) -> Self {
+            expect_valid(Self::convert_with_encoding(buffer, UTF_8, DecodePolicy::Lossy))
+        }
+
+        fn column_size(&self) -> ffi::SQLULEN {
+            timestamp_column_size(self.nanosecond())
+        }
+        fn decimal_digits(&self) -> ffi::SQLSMALLINT {
+            fraction_digits(self.nanosecond())
+        }
+        fn value_ptr(&self) -> ffi::SQLPOINTER {
+            self as *const Self as ffi::SQLPOINTER
+        }
+
+        fn encoded_value(&self) -> EncodedValue {
+            expect_valid(self.encoded_value_with_encoding(UTF_8))
+        }
+
+        fn convert_with_encoding(
+            buffer: &'a [u8],
+            encoding: &'static Encoding,
+            policy: DecodePolicy,
+        ) -> ::Result<Self> {
+            from_sql_timestamp(&SqlTimestamp::convert_with_encoding(buffer, encoding, policy)?)
+        }
+
+        fn encoded_value_with_encoding(
+            &self,
+            _encoding: &'static Encoding,
+        ) -> ::Result<EncodedValue> {
+            to_sql_timestamp(self).map(|timestamp| encode_timestamp(&timestamp))
+        }
+    }
+
+    /// Exchanged as timestamp without time zone in UTC. Values with other offsets are converted
+    /// to UTC when bound.
+    unsafe impl<'a> OdbcType<'a> for OffsetDateTime {
+        fn sql_data_type() -> ffi::SqlDataType {
+            SqlTimestamp::sql_data_type()
+        }
+        fn c_data_type() -> ffi::SqlCDataType {
+            SqlTimestamp::c_data_type()
+        }
+
+        #[allow(deprecated)]
+        fn convert(buffer: &'a [u8]) -> Self {
+            PrimitiveDateTime::convert(buffer).assume_utc()
+        }
+
+        fn column_size(&self) -> ffi::SQLULEN {
+            timestamp_column_size(self.nanosecond())
+        }
+        fn decimal_digits(&self) -> ffi::SQLSMALLINT {
+            fraction_digits(self.nanosecond())
+        }
+        fn value_ptr(&self) -> ffi::SQLPOINTER {
+            self as *const Self as ffi::SQLPOINTER
+        }
+
+        fn encoded_value(&self) -> EncodedValue {
+            expect_valid(self.encoded_value_with_encoding(UTF_8))
+        }
+
+        fn convert_with_encoding(
+            buffer: &'a [u8],
+            encoding: &'static Encoding,
+            policy: DecodePolicy,
+        ) -> ::Result<Self> {
+            PrimitiveDateTime::convert_with_encoding(buffer, encoding, policy)
+                .map(PrimitiveDateTime::assume_utc)
+        }
+
+        fn encoded_value_with_encoding(
+            &self,
+            encoding: &'static Encoding,
+        ) -> ::Result<EncodedValue> {
+            let utc = self.to_offset(UtcOffset::UTC);
+            PrimitiveDateTime::new(utc.date(), utc.time()).encoded_value_with_encoding(encoding)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fraction_precision() {
+        assert_eq!(fraction_digits(0), 0);
+        assert_eq!(fraction_digits(500_000_000), 3);
+        assert_eq!(fraction_digits(123_456_000), 6);
+        assert_eq!(fraction_digits(1), 9);
+        assert_eq!(timestamp_column_size(0), 19);
+        assert_eq!(timestamp_column_size(123_456_000), 26);
+    }
+
+    #[test]
+    fn leap_second() {
+        assert_eq!(sql_fraction(1_500_000_000), 999_999_999);
+    }
+
+    #[test]
+    fn year_out_of_range() {
+        assert_eq!(sql_year(-32_768).unwrap(), i16::MIN);
+        assert_eq!(sql_year(40_000).unwrap_err().get_raw_state(), b"22008\0");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn invalid_chrono_date() {
+        use chrono::NaiveDate;
+        let date = SqlDate { year: 2021, month: 2, day: 30 };
+        let error = NaiveDate::convert_with_encoding(&bytes_of(&date), UTF_8, DecodePolicy::Lossy)
+            .unwrap_err();
+        assert_eq!(error.get_raw_state(), b"22007\0");
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn invalid_time_timestamp() {
+        use time::PrimitiveDateTime;
+        let timestamp = SqlTimestamp {
+            year: 2021,
+            month: 1,
+            day: 1,
+            hour: 25,
+            minute: 0,
+            second: 0,
+            fraction: 0,
+        };
+        let buffer = bytes_of(&timestamp);
+        let error = PrimitiveDateTime::convert_with_encoding(&buffer, UTF_8, DecodePolicy::Lossy)
+            .unwrap_err();
+        assert_eq!(error.get_raw_state(), b"22007\0");
+    }
+}
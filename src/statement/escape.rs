@@ -0,0 +1,162 @@
+//! Builders for ODBC escape sequences
+//!
+//! Drivers replace escape sequences with the native syntax of the data source, which allows
+//! writing portable SQL for literals and clauses which differ between vendors. Use
+//! `Connection::native_sql` to inspect the translated text.
+//!
+//! # Example
+//! ```
+//! # use odbc::*;
+//! let date = SqlDate { year: 2021, month: 3, day: 14 };
+//! let sql = format!(
+//!     "SELECT {} FROM {} WHERE RELEASED > {}",
+//!     escape::function("UCASE", &["M.TITLE"]),
+//!     escape::outer_join("MOVIES M LEFT OUTER JOIN RATINGS R ON M.ID = R.MOVIE_ID"),
+//!     escape::date(&date),
+//! );
+//! assert_eq!(
+//!     sql,
+//!     "SELECT {fn UCASE(M.TITLE)} FROM {oj MOVIES M LEFT OUTER JOIN RATINGS R ON M.ID = R.MOVIE_ID} \
+//!      WHERE RELEASED > {d '2021-03-14'}"
+//! );
+//! ```
+use super::types::fraction_digits;
+use {SqlDate, SqlTime, SqlTimestamp};
+
+/// Date literal, e.g. `{d '2021-03-14'}`
+pub fn date(value: &SqlDate) -> String {
+    format!("{{d '{:04}-{:02}-{:02}'}}", value.year, value.month, value.day)
+}
+
+/// Time literal, e.g. `{t '15:09:26'}`
+pub fn time(value: &SqlTime) -> String {
+    format!("{{t '{:02}:{:02}:{:02}'}}", value.hour, value.minute, value.second)
+}
+
+/// Timestamp literal, e.g. `{ts '2021-03-14 15:09:26.535'}`. Fractional seconds are given with
+/// as many digits as needed, up to nanoseconds.
+pub fn timestamp(value: &SqlTimestamp) -> String {
+    let fraction = match fraction_digits(value.fraction) {
+        0 => String::new(),
+        digits => format!(".{:09}", value.fraction)[..digits as usize + 1].to_owned(),
+    };
+    format!(
+        "{{ts '{:04}-{:02}-{:02} {:02}:{:02}:{:02}{}'}}",
+        value.year, value.month, value.day, value.hour, value.minute, value.second, fraction
+    )
+}
+
+/// Character string literal, enclosed in single quotes. Single quotes within `text` are doubled.
+pub fn string_literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// Scalar function call, e.g. `{fn CONCAT(A, B)}`. `arguments` are inserted as is.
+pub fn function(name: &str, arguments: &[&str]) -> String {
+    format!("{{fn {}({})}}", name, arguments.join(", "))
+}
+
+/// Procedure call with `num_params` parameter markers, e.g. `{call UPDATE_RATING(?, ?)}`
+pub fn call(procedure: &str, num_params: usize) -> String {
+    format!("{{call {}{}}}", procedure, markers(num_params))
+}
+
+/// Procedure call like `call`, with an additional parameter marker in front receiving the return
+/// value of the procedure, e.g. `{? = call COUNT_MOVIES(?)}`
+pub fn call_with_return(procedure: &str, num_params: usize) -> String {
+    format!("{{? = call {}{}}}", procedure, markers(num_params))
+}
+
+/// Parenthesized list of `num_params` parameter markers. Empty if there are none.
+fn markers(num_params: usize) -> String {
+    if num_params == 0 {
+        String::new()
+    } else {
+        format!("({})", vec!["?"; num_params].join(", "))
+    }
+}
+
+/// Outer join, e.g. `{oj A LEFT OUTER JOIN B ON A.ID = B.A_ID}`. `join` is inserted as is.
+pub fn outer_join(join: &str) -> String {
+    format!("{{oj {}}}", join)
+}
+
+/// Escapes the wildcards `%` and `_` in `text` with `escape`, so it matches literally in a `LIKE`
+/// pattern. Occurrences of `escape` itself are escaped, too. Combine it with `like_escape`, to
+/// declare the escape character.
+///
+/// # Example
+/// ```
+/// # use odbc::*;
+/// let pattern = format!("{}%", escape::escape_like("100%_sure", '\\'));
+/// let sql = format!(
+///     "SELECT TITLE FROM MOVIES WHERE TITLE LIKE {} {}",
+///     escape::string_literal(&pattern),
+///     escape::like_escape('\\'),
+/// );
+/// assert_eq!(sql, r"SELECT TITLE FROM MOVIES WHERE TITLE LIKE '100\%\_sure%' {escape '\'}");
+/// ```
+pub fn escape_like(text: &str, escape: char) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '%' || c == '_' || c == escape {
+            escaped.push(escape);
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// `LIKE` escape clause declaring `escape` as escape character, e.g. `{escape '\'}`
+pub fn like_escape(escape: char) -> String {
+    format!("{{escape {}}}", string_literal(&escape.to_string()))
+}
+
+/// Clause limiting a result set to `count` rows, after skipping the first `offset` rows
+///
+/// ODBC defines no escape sequence for this, so the clause uses the SQL:2008 syntax
+/// `OFFSET ... ROWS FETCH NEXT ... ROWS ONLY`, which is understood by most data sources. The
+/// result set should be ordered, for the rows skipped to be well defined.
+pub fn limit(count: u64, offset: u64) -> String {
+    format!("OFFSET {} ROWS FETCH NEXT {} ROWS ONLY", offset, count)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn date_time_literals() {
+        let ts = SqlTimestamp {
+            year: 2021,
+            month: 3,
+            day: 14,
+            hour: 5,
+            minute: 9,
+            second: 26,
+            fraction: 535_897_000,
+        };
+        assert_eq!(timestamp(&ts), "{ts '2021-03-14 05:09:26.535897'}");
+        assert_eq!(timestamp(&SqlTimestamp { fraction: 0, ..ts }), "{ts '2021-03-14 05:09:26'}");
+        assert_eq!(
+            timestamp(&SqlTimestamp { fraction: 500_000_000, ..ts }),
+            "{ts '2021-03-14 05:09:26.500'}"
+        );
+        assert_eq!(time(&SqlTime { hour: 5, minute: 9, second: 26 }), "{t '05:09:26'}");
+    }
+
+    #[test]
+    fn procedure_calls() {
+        assert_eq!(call("RESET", 0), "{call RESET}");
+        assert_eq!(call("UPDATE_RATING", 2), "{call UPDATE_RATING(?, ?)}");
+        assert_eq!(call_with_return("COUNT_MOVIES", 1), "{? = call COUNT_MOVIES(?)}");
+    }
+
+    #[test]
+    fn literals_and_like() {
+        assert_eq!(string_literal("it's"), "'it''s'");
+        assert_eq!(escape_like("a%b_c!", '!'), "a!%b!_c!!");
+        assert_eq!(like_escape('\''), "{escape ''''}");
+        assert_eq!(function("NOW", &[]), "{fn NOW()}");
+    }
+}
@@ -1,7 +1,7 @@
 use super::types::OdbcType;
 use odbc_safe::AutocommitMode;
 use statement::types::EncodedValue;
-use {ffi, Handle, Raii, Result, Return, Statement};
+use {ffi, Error, Handle, HasEncodings, ParameterDescriptor, Raii, Result, Return, Statement};
 
 impl<'a, 'b, S, R, AC: AutocommitMode> Statement<'a, 'b, S, R, AC> {
     /// Binds a parameter to a parameter marker in an SQL statement.
@@ -32,7 +32,7 @@ impl<'a, 'b, S, R, AC: AutocommitMode> Statement<'a, 'b, S, R, AC> {
     /// # }
     /// ```
     pub fn bind_parameter<'c, T>(
-        mut self,
+        self,
         parameter_index: u16,
         value: &'c T,
     ) -> Result<Statement<'a, 'c, S, R, AC>>
@@ -41,21 +41,64 @@ impl<'a, 'b, S, R, AC: AutocommitMode> Statement<'a, 'b, S, R, AC> {
         T: ?Sized,
         'b: 'c,
     {
+        self.bind(Parameter::new(parameter_index, value))
+    }
+
+    /// Binds a parameter like `bind_parameter`, but allows overriding the SQL data type, column
+    /// size and decimal digits derived from the value.
+    ///
+    /// # Example
+    /// ```
+    /// # use odbc::*;
+    /// # fn do_odbc_stuff() -> std::result::Result<(), Box<std::error::Error>> {
+    /// let env = create_environment_v3().map_err(|e| e.unwrap())?;
+    /// let conn = env.connect("TestDataSource", "", "")?;
+    /// let stmt = Statement::with_parent(&conn)?;
+    /// let stmt = stmt.prepare("SELECT TITLE FROM MOVIES WHERE TITLE = ?")?;
+    /// let title = "2001: A Space Odyssey";
+    /// let stmt = stmt.bind(
+    ///     Parameter::new(1, &title).sql_type(ffi::SQL_VARCHAR).column_size(200),
+    /// )?;
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn bind<'c, T>(mut self, parameter: Parameter<'c, T>) -> Result<Statement<'a, 'c, S, R, AC>>
+    where
+        T: OdbcType<'c>,
+        'b: 'c,
+    {
+        let parameter_index = parameter.index;
+        let value = parameter.value;
+
+        //the result of value_ptr is changed per calling.
+        //binding and saving must have the same value.
+        let enc_value = value.encoded_value_with_encoding(self.raii.encodings().db)?;
+
         let ind = if value.value_ptr() == 0 as *const Self as ffi::SQLPOINTER {
             ffi::SQL_NULL_DATA
+        } else if enc_value.has_value() {
+            enc_value.buffer_length()
         } else {
             value.column_size() as ffi::SQLLEN
         };
 
         let ind_ptr = self.param_ind_buffers.alloc(parameter_index as usize, ind);
 
-        //the result of value_ptr is changed per calling.
-        //binding and saving must have the same value.
-        let enc_value = value.encoded_value();
+        let described = if parameter.describe {
+            // Drivers not supporting `SQLDescribeParam` fall back to the types of the value
+            match self.raii.describe_param(parameter_index).into_result(&self) {
+                Ok(description) => Some(description),
+                Err(ref error) if is_not_supported(error) => None,
+                Err(error) => return Err(error),
+            }
+        } else {
+            None
+        };
 
         self.raii
-            .bind_input_parameter(parameter_index, value, ind_ptr, &enc_value)
+            .bind_input_parameter(&parameter, described, ind_ptr, &enc_value)
             .into_result(&self)?;
+        value.bind_descriptor(&mut self.raii, parameter_index)?;
 
         // save encoded value to avoid memory reuse.
         if enc_value.has_value() {
@@ -75,11 +118,115 @@ impl<'a, 'b, S, R, AC: AutocommitMode> Statement<'a, 'b, S, R, AC> {
     }
 }
 
+/// `true` if `SQLDescribeParam` failed because the driver does not implement it. The driver
+/// manager reports missing functions as `IM001`, drivers as `HYC00` or as a general error saying
+/// so.
+fn is_not_supported(error: &Error) -> bool {
+    let state = error.sql_state();
+    state == "IM001"
+        || state == "HYC00"
+        || state == "HY000"
+            && String::from_utf8_lossy(error.get_raw_message())
+                .to_lowercase()
+                .contains("not supported")
+}
+
+/// A value to bind to a parameter marker, along with the SQL type it is bound as
+///
+/// By default, SQL data type, column size and decimal digits are derived from the value. Each can
+/// be overridden, e.g. to bind a string as `VARCHAR(n)` instead of the default, or to bind
+/// decimals with the precision and scale of the target column. Use `Statement::bind` to bind it.
+pub struct Parameter<'c, T: 'c> {
+    index: u16,
+    value: &'c T,
+    sql_type: Option<ffi::SQLSMALLINT>,
+    column_size: Option<ffi::SQLULEN>,
+    decimal_digits: Option<ffi::SQLSMALLINT>,
+    describe: bool,
+}
+
+impl<'c, T> Parameter<'c, T>
+where
+    T: OdbcType<'c>,
+{
+    /// Parameter binding `value` to the marker at `index`. Starting at `1`.
+    pub fn new(index: u16, value: &'c T) -> Self {
+        Parameter {
+            index,
+            value,
+            sql_type: None,
+            column_size: None,
+            decimal_digits: None,
+            describe: false,
+        }
+    }
+
+    /// SQL data type of the parameter, instead of `OdbcType::sql_data_type`
+    pub fn sql_type(mut self, sql_type: ffi::SqlDataType) -> Self {
+        self.sql_type = Some(sql_type as ffi::SQLSMALLINT);
+        self
+    }
+
+    /// Column size of the parameter, e.g. the maximum length of a `VARCHAR` or the precision of a
+    /// `DECIMAL`, instead of the size of the value
+    pub fn column_size(mut self, column_size: usize) -> Self {
+        self.column_size = Some(column_size as ffi::SQLULEN);
+        self
+    }
+
+    /// Decimal digits of the parameter, e.g. the scale of a `DECIMAL`
+    pub fn decimal_digits(mut self, decimal_digits: i16) -> Self {
+        self.decimal_digits = Some(decimal_digits);
+        self
+    }
+
+    /// Infers SQL data type, column size and decimal digits from `SQLDescribeParam`
+    ///
+    /// Values set explicitly still take precedence. If the driver does not support
+    /// `SQLDescribeParam`, the values derived from the value are used. Other errors describing the
+    /// parameter, e.g. because the statement is not prepared yet, are returned by `bind`.
+    pub fn auto(mut self) -> Self {
+        self.describe = true;
+        self
+    }
+}
+
+/// SQL data type, column size and decimal digits of a parameter as reported by the driver
+pub(super) struct ParamDescription {
+    pub data_type: ffi::SQLSMALLINT,
+    pub column_size: ffi::SQLULEN,
+    pub decimal_digits: ffi::SQLSMALLINT,
+    pub nullable: ffi::SQLSMALLINT,
+}
+
+impl ParamDescription {
+    pub(super) fn to_descriptor(&self) -> ParameterDescriptor {
+        ParameterDescriptor {
+            data_type: ffi::sql_data_type(self.data_type),
+            column_size: if self.column_size == 0 {
+                None
+            } else {
+                Some(self.column_size)
+            },
+            decimal_digits: if self.decimal_digits == 0 {
+                None
+            } else {
+                Some(self.decimal_digits as u16)
+            },
+            nullable: match self.nullable {
+                0 => Some(false),
+                1 => Some(true),
+                _ => None,
+            },
+        }
+    }
+}
+
 impl<'p> Raii<'p, ffi::Stmt> {
     fn bind_input_parameter<'c, T>(
         &mut self,
-        parameter_index: u16,
-        value: &'c T,
+        parameter: &Parameter<'c, T>,
+        described: Option<ParamDescription>,
         str_len_or_ind_ptr: *mut ffi::SQLLEN,
         enc_value: &EncodedValue,
     ) -> Return<()>
@@ -87,6 +234,7 @@ impl<'p> Raii<'p, ffi::Stmt> {
         T: OdbcType<'c>,
         T: ?Sized,
     {
+        let value = parameter.value;
         //if encoded value exists, use it.
         let (column_size, value_ptr) = if enc_value.has_value() {
             (enc_value.column_size(), enc_value.value_ptr())
@@ -94,15 +242,26 @@ impl<'p> Raii<'p, ffi::Stmt> {
             (value.column_size(), value.value_ptr())
         };
 
+        let (c_type, sql_type) = T::bind_types(Some(value));
+        // Explicit settings take precedence over the description of the driver, which takes
+        // precedence over the value
+        let (sql_type, column_size, decimal_digits) = match described {
+            Some(d) => (d.data_type, d.column_size, d.decimal_digits),
+            None => (sql_type, column_size, value.decimal_digits()),
+        };
+        let sql_type = parameter.sql_type.unwrap_or(sql_type);
+        let column_size = parameter.column_size.unwrap_or(column_size);
+        let decimal_digits = parameter.decimal_digits.unwrap_or(decimal_digits);
+
         match unsafe {
-            ffi::SQLBindParameter(
+            ffi::SQLBindParameterWithTypes(
                 self.handle(),
-                parameter_index,
+                parameter.index,
                 ffi::SQL_PARAM_INPUT,
-                T::c_data_type(),
-                T::sql_data_type(),
+                c_type,
+                sql_type,
                 column_size,
-                value.decimal_digits(),
+                decimal_digits,
                 value_ptr,
                 0,                  // buffer length
                 str_len_or_ind_ptr, // Note that this ptr has to be valid until statement is executed
@@ -110,16 +269,51 @@ impl<'p> Raii<'p, ffi::Stmt> {
         } {
             ffi::SQL_SUCCESS => Return::Success(()),
             ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(()),
-            ffi::SQL_ERROR => Return::Error,
+            ffi::SQL_ERROR => Return::Error("SQLBindParameter"),
             r => panic!("Unexpected return from SQLBindParameter: {:?}", r),
         }
     }
 
+    pub(super) fn num_params(&self) -> Return<i16> {
+        let mut num_params: ffi::SQLSMALLINT = 0;
+        match unsafe { ffi::SQLNumParams(self.handle(), &mut num_params as *mut ffi::SQLSMALLINT) } {
+            ffi::SQL_SUCCESS => Return::Success(num_params),
+            ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(num_params),
+            ffi::SQL_ERROR => Return::Error("SQLNumParams"),
+            r => panic!("SQLNumParams returned unexpected result: {:?}", r),
+        }
+    }
+
+    /// Describes the parameter marker at `parameter_index`
+    pub(super) fn describe_param(&self, parameter_index: u16) -> Return<ParamDescription> {
+        let mut description = ParamDescription {
+            data_type: 0,
+            column_size: 0,
+            decimal_digits: 0,
+            nullable: 0,
+        };
+        match unsafe {
+            ffi::SQLDescribeParamWithType(
+                self.handle(),
+                parameter_index,
+                &mut description.data_type as *mut ffi::SQLSMALLINT,
+                &mut description.column_size as *mut ffi::SQLULEN,
+                &mut description.decimal_digits as *mut ffi::SQLSMALLINT,
+                &mut description.nullable as *mut ffi::SQLSMALLINT,
+            )
+        } {
+            ffi::SQL_SUCCESS => Return::Success(description),
+            ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(description),
+            ffi::SQL_ERROR => Return::Error("SQLDescribeParam"),
+            r => panic!("SQLDescribeParam returned unexpected result: {:?}", r),
+        }
+    }
+
     fn reset_parameters(&mut self) -> Return<()> {
         match unsafe { ffi::SQLFreeStmt(self.handle(), ffi::SQL_RESET_PARAMS) } {
             ffi::SQL_SUCCESS => Return::Success(()),
             ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(()),
-            ffi::SQL_ERROR => Return::Error,
+            ffi::SQL_ERROR => Return::Error("SQLFreeStmt"),
             r => panic!("SQLFreeStmt returned unexpected result: {:?}", r),
         }
     }
@@ -0,0 +1,426 @@
+//! Year-month and day-time intervals exchanged as `SQL_INTERVAL_STRUCT`
+//!
+//! The C and SQL type of an interval depend on its subtype, which is only known at runtime. So
+//! binding a `SqlInterval` uses the subtype of the bound value and fetching one uses the subtype of
+//! the column. Drivers assume a leading field precision of two digits unless told otherwise, so
+//! both set the interval fields of the application descriptor record as well.
+use super::types::{EncodedValue, OdbcType};
+use std::convert::TryFrom;
+use std::fmt;
+use std::mem::size_of;
+use std::ptr::{null_mut, read_unaligned};
+use std::time::Duration;
+use {ffi, Handle, Raii, Result, Return};
+
+/// Subtype of a `SqlInterval`, e.g. `SqlIntervalType::SQL_IS_DAY_TO_SECOND`
+pub type SqlIntervalType = ffi::SQLINTERVAL;
+
+/// Number of digits of the leading field of intervals exchanged with the driver
+const LEADING_PRECISION: ffi::SQLSMALLINT = 9;
+/// Number of fractional second digits of intervals exchanged with the driver. `fraction` is
+/// therefore measured in microseconds.
+const SECONDS_PRECISION: ffi::SQLSMALLINT = 6;
+/// Difference between the concise C or SQL type of an interval and its interval code
+const INTERVAL_TYPE_OFFSET: ffi::SQLSMALLINT = 100;
+
+/// Interval value, e.g. of an `INTERVAL DAY TO SECOND` column, wrapping `SQL_INTERVAL_STRUCT`
+///
+/// Year-month intervals (`SQL_IS_YEAR`, `SQL_IS_MONTH` and `SQL_IS_YEAR_TO_MONTH`) store years and
+/// months, all other subtypes store days, hours, minutes, seconds and a fraction of microseconds.
+///
+/// # Example
+/// ```
+/// # use odbc::*;
+/// # use std::time::Duration;
+/// let interval = SqlInterval::day_second(SqlIntervalType::SQL_IS_DAY_TO_SECOND, false, 1, 2, 3, 4, 500_000);
+/// assert_eq!(interval.to_duration(), Some(Duration::from_millis(93_784_500)));
+/// ```
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct SqlInterval(pub ffi::SQL_INTERVAL_STRUCT);
+
+impl SqlInterval {
+    /// Creates a year-month interval
+    ///
+    /// # Panics
+    /// If `interval_type` is not one of `SQL_IS_YEAR`, `SQL_IS_MONTH` or `SQL_IS_YEAR_TO_MONTH`
+    pub fn year_month(interval_type: SqlIntervalType, negative: bool, year: u32, month: u32) -> SqlInterval {
+        assert!(is_year_month(interval_type), "{:?} is not a year-month interval", interval_type);
+        SqlInterval(ffi::SQL_INTERVAL_STRUCT {
+            interval_type,
+            interval_sign: negative as ffi::SQLSMALLINT,
+            interval_value: ffi::SQL_INTERVAL_UNION {
+                year_month: ffi::SQL_YEAR_MONTH_STRUCT { year, month },
+            },
+        })
+    }
+
+    /// Creates a day-time interval. `fraction` is measured in microseconds.
+    ///
+    /// # Panics
+    /// If `interval_type` is a year-month interval type
+    pub fn day_second(
+        interval_type: SqlIntervalType,
+        negative: bool,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+        fraction: u32,
+    ) -> SqlInterval {
+        assert!(!is_year_month(interval_type), "{:?} is not a day-time interval", interval_type);
+        SqlInterval(ffi::SQL_INTERVAL_STRUCT {
+            interval_type,
+            interval_sign: negative as ffi::SQLSMALLINT,
+            interval_value: ffi::SQL_INTERVAL_UNION {
+                day_second: ffi::SQL_DAY_SECOND_STRUCT { day, hour, minute, second, fraction },
+            },
+        })
+    }
+
+    /// Creates a `SQL_IS_DAY_TO_SECOND` interval from a `Duration`, truncated to microseconds
+    ///
+    /// Fails if the duration has more days than fit into an `u32`.
+    pub fn from_duration(duration: Duration) -> ::std::result::Result<SqlInterval, IntervalRangeError> {
+        let secs = duration.as_secs();
+        let day = u32::try_from(secs / 86_400).map_err(|_| IntervalRangeError)?;
+        Ok(SqlInterval::day_second(
+            SqlIntervalType::SQL_IS_DAY_TO_SECOND,
+            false,
+            day,
+            (secs % 86_400 / 3_600) as u32,
+            (secs % 3_600 / 60) as u32,
+            (secs % 60) as u32,
+            duration.subsec_micros(),
+        ))
+    }
+
+    /// Subtype of the interval
+    pub fn interval_type(&self) -> SqlIntervalType {
+        self.0.interval_type
+    }
+
+    /// `true` for intervals storing years and months
+    pub fn is_year_month(&self) -> bool {
+        is_year_month(self.0.interval_type)
+    }
+
+    /// `true` if the interval is negative
+    pub fn is_negative(&self) -> bool {
+        self.0.interval_sign != 0
+    }
+
+    /// Years and months of a year-month interval
+    pub fn as_year_month(&self) -> Option<&ffi::SQL_YEAR_MONTH_STRUCT> {
+        if self.is_year_month() {
+            Some(unsafe { &self.0.interval_value.year_month })
+        } else {
+            None
+        }
+    }
+
+    /// Days, hours, minutes, seconds and microseconds of a day-time interval
+    pub fn as_day_second(&self) -> Option<&ffi::SQL_DAY_SECOND_STRUCT> {
+        if self.is_year_month() {
+            None
+        } else {
+            Some(unsafe { &self.0.interval_value.day_second })
+        }
+    }
+
+    /// Signed number of months of a year-month interval
+    pub fn total_months(&self) -> Option<i64> {
+        self.as_year_month().map(|ym| {
+            let months = ym.year as i64 * 12 + ym.month as i64;
+            if self.is_negative() { -months } else { months }
+        })
+    }
+
+    /// Signed number of microseconds of a day-time interval
+    pub fn total_microseconds(&self) -> Option<i128> {
+        self.as_day_second().map(|ds| {
+            let secs = ds.day as i128 * 86_400 + ds.hour as i128 * 3_600 + ds.minute as i128 * 60
+                + ds.second as i128;
+            let micros = secs * 1_000_000 + ds.fraction as i128;
+            if self.is_negative() { -micros } else { micros }
+        })
+    }
+
+    /// Length of a day-time interval, or `None` for negative and year-month intervals, since
+    /// neither can be represented by a `Duration`.
+    pub fn to_duration(&self) -> Option<Duration> {
+        let micros = self.total_microseconds()?;
+        if micros < 0 {
+            return None;
+        }
+        let micros = micros as u128;
+        Some(Duration::new((micros / 1_000_000) as u64, (micros % 1_000_000) as u32 * 1_000))
+    }
+}
+
+fn is_year_month(interval_type: SqlIntervalType) -> bool {
+    matches!(
+        interval_type,
+        SqlIntervalType::SQL_IS_YEAR | SqlIntervalType::SQL_IS_MONTH | SqlIntervalType::SQL_IS_YEAR_TO_MONTH
+    )
+}
+
+fn has_seconds(interval_type: SqlIntervalType) -> bool {
+    matches!(
+        interval_type,
+        SqlIntervalType::SQL_IS_SECOND
+            | SqlIntervalType::SQL_IS_DAY_TO_SECOND
+            | SqlIntervalType::SQL_IS_HOUR_TO_SECOND
+            | SqlIntervalType::SQL_IS_MINUTE_TO_SECOND
+    )
+}
+
+impl fmt::Debug for SqlInterval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = f.debug_struct("SqlInterval");
+        s.field("interval_type", &self.interval_type());
+        s.field("negative", &self.is_negative());
+        match (self.as_year_month(), self.as_day_second()) {
+            (Some(ym), _) => s.field("year_month", ym),
+            (_, Some(ds)) => s.field("day_second", ds),
+            _ => unreachable!(),
+        };
+        s.finish()
+    }
+}
+
+impl PartialEq for SqlInterval {
+    fn eq(&self, other: &SqlInterval) -> bool {
+        self.interval_type() == other.interval_type()
+            && self.is_negative() == other.is_negative()
+            && self.as_year_month() == other.as_year_month()
+            && self.as_day_second() == other.as_day_second()
+    }
+}
+
+impl Eq for SqlInterval {}
+
+impl TryFrom<Duration> for SqlInterval {
+    type Error = IntervalRangeError;
+
+    fn try_from(value: Duration) -> ::std::result::Result<SqlInterval, IntervalRangeError> {
+        SqlInterval::from_duration(value)
+    }
+}
+
+impl TryFrom<SqlInterval> for Duration {
+    type Error = IntervalRangeError;
+
+    fn try_from(value: SqlInterval) -> ::std::result::Result<Duration, IntervalRangeError> {
+        value.to_duration().ok_or(IntervalRangeError)
+    }
+}
+
+unsafe impl<'a> OdbcType<'a> for SqlInterval {
+    // The actual types depend on the subtype of the value or the column, see `bind_types` and
+    // `get_data_target_type`
+    fn sql_data_type() -> ffi::SqlDataType {
+        ffi::SQL_EXT_TIME_OR_INTERVAL
+    }
+    fn c_data_type() -> ffi::SqlCDataType {
+        ffi::SQL_C_INTERVAL_DAY_TO_SECOND
+    }
+
+    fn convert(buffer: &'a [u8]) -> Self {
+        assert_eq!(buffer.len(), size_of::<Self>());
+        unsafe { read_unaligned(buffer.as_ptr() as *const Self) }
+    }
+
+    /// Number of characters of the interval literal
+    fn column_size(&self) -> ffi::SQLULEN {
+        use ffi::SQLINTERVAL::*;
+        let trailing_fields = match self.interval_type() {
+            SQL_IS_YEAR | SQL_IS_MONTH | SQL_IS_DAY | SQL_IS_HOUR | SQL_IS_MINUTE | SQL_IS_SECOND => 0,
+            SQL_IS_YEAR_TO_MONTH | SQL_IS_DAY_TO_HOUR | SQL_IS_HOUR_TO_MINUTE
+            | SQL_IS_MINUTE_TO_SECOND => 1,
+            SQL_IS_DAY_TO_MINUTE | SQL_IS_HOUR_TO_SECOND => 2,
+            SQL_IS_DAY_TO_SECOND => 3,
+        };
+        let fraction = if has_seconds(self.interval_type()) { 1 + SECONDS_PRECISION } else { 0 };
+        (LEADING_PRECISION + trailing_fields * 3 + fraction) as ffi::SQLULEN
+    }
+
+    fn decimal_digits(&self) -> ffi::SQLSMALLINT {
+        if has_seconds(self.interval_type()) { SECONDS_PRECISION } else { 0 }
+    }
+
+    fn value_ptr(&self) -> ffi::SQLPOINTER {
+        &self.0 as *const ffi::SQL_INTERVAL_STRUCT as ffi::SQLPOINTER
+    }
+
+    fn encoded_value(&self) -> EncodedValue {
+        EncodedValue::new(None)
+    }
+
+    /// Types of the subtype of the value. `NULL` values are bound as `SQL_INTERVAL_DAY_TO_SECOND`.
+    fn bind_types(value: Option<&Self>) -> (ffi::SQLSMALLINT, ffi::SQLSMALLINT) {
+        let interval_type = value.map_or(SqlIntervalType::SQL_IS_DAY_TO_SECOND, SqlInterval::interval_type);
+        let concise_type = INTERVAL_TYPE_OFFSET + interval_type as ffi::SQLSMALLINT;
+        (concise_type, concise_type)
+    }
+
+    fn bind_descriptor(&self, stmt: &mut Raii<ffi::Stmt>, parameter_index: u16) -> Result<()> {
+        stmt.set_interval_parameter(parameter_index, self)
+    }
+
+    fn get_data_target_type(stmt: &mut Raii<ffi::Stmt>, col: u16) -> Result<ffi::SQLSMALLINT> {
+        stmt.set_interval_target(col)?;
+        Ok(ffi::SQL_ARD_TYPE)
+    }
+}
+
+impl<'p> Raii<'p, ffi::Stmt> {
+    /// Sets the leading and fractional seconds precision of the APD record of a parameter bound to
+    /// a `SqlInterval`. The data pointer is set last, since setting the other fields unbinds the
+    /// record.
+    fn set_interval_parameter(&mut self, parameter_index: u16, value: &SqlInterval) -> Result<()> {
+        let apd = self.app_descriptor(ffi::SQL_ATTR_APP_PARAM_DESC)?;
+        let interval_code = value.interval_type() as ffi::SQLSMALLINT;
+        self.set_interval_record(apd, parameter_index, interval_code)?;
+        self.set_desc_field(apd, parameter_index, ffi::SQL_DESC_DATA_PTR, value.value_ptr())
+    }
+
+    /// Prepares the ARD record of a column, so `SQLGetData` with `SQL_ARD_TYPE` retrieves it as
+    /// interval of the same subtype as the column. Columns which are not intervals, e.g. interval
+    /// literals stored as text, are retrieved as `SQL_IS_DAY_TO_SECOND`.
+    fn set_interval_target(&mut self, col: u16) -> Result<()> {
+        let concise_type = self.concise_type(col).into_result(self)?;
+        let interval_code = match concise_type - INTERVAL_TYPE_OFFSET {
+            code @ 1..=13 => code,
+            _ => SqlIntervalType::SQL_IS_DAY_TO_SECOND as ffi::SQLSMALLINT,
+        };
+        let ard = self.app_descriptor(ffi::SQL_ATTR_APP_ROW_DESC)?;
+        self.set_interval_record(ard, col, interval_code)
+    }
+
+    fn set_interval_record(
+        &self,
+        desc: ffi::SQLHDESC,
+        record: u16,
+        interval_code: ffi::SQLSMALLINT,
+    ) -> Result<()> {
+        let fields = [
+            (ffi::SQL_DESC_TYPE, ffi::SQL_INTERVAL),
+            (ffi::SQL_DESC_DATETIME_INTERVAL_CODE, interval_code),
+            (ffi::SQL_DESC_DATETIME_INTERVAL_PRECISION, LEADING_PRECISION),
+            (ffi::SQL_DESC_PRECISION, SECONDS_PRECISION),
+        ];
+        for &(field, value) in &fields {
+            self.set_desc_field(desc, record, field, value as isize as ffi::SQLPOINTER)?;
+        }
+        Ok(())
+    }
+
+    /// Concise SQL data type of a column. Unlike `describe_col` this also covers the interval
+    /// types.
+    pub(super) fn concise_type(&self, col: u16) -> Return<ffi::SQLSMALLINT> {
+        let mut concise_type: ffi::SQLLEN = 0;
+        match unsafe {
+            // Numeric attributes are not affected by the character encoding
+            ffi::SQLColAttributeW(
+                self.handle(),
+                col,
+                ffi::SQL_DESC_CONCISE_TYPE,
+                null_mut(),
+                0,
+                null_mut(),
+                &mut concise_type as *mut ffi::SQLLEN,
+            )
+        } {
+            ffi::SQL_SUCCESS => Return::Success(concise_type as ffi::SQLSMALLINT),
+            ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(concise_type as ffi::SQLSMALLINT),
+            ffi::SQL_ERROR => Return::Error("SQLColAttribute"),
+            r => panic!("SQLColAttribute returned unexpected result: {:?}", r),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_conversions {
+    use super::{IntervalRangeError, SqlInterval};
+    use chrono;
+    use std::convert::TryFrom;
+
+    impl TryFrom<chrono::Duration> for SqlInterval {
+        type Error = IntervalRangeError;
+
+        /// Creates a `SQL_IS_DAY_TO_SECOND` interval, truncated to microseconds
+        fn try_from(value: chrono::Duration) -> Result<SqlInterval, IntervalRangeError> {
+            let micros = value.num_microseconds().ok_or(IntervalRangeError)?;
+            let magnitude = ::std::time::Duration::from_micros(micros.unsigned_abs());
+            let mut interval = SqlInterval::from_duration(magnitude)?;
+            interval.0.interval_sign = (micros < 0) as i16;
+            Ok(interval)
+        }
+    }
+
+    impl TryFrom<SqlInterval> for chrono::Duration {
+        type Error = IntervalRangeError;
+
+        /// Fails for year-month intervals, since the length of a month varies
+        fn try_from(value: SqlInterval) -> Result<chrono::Duration, IntervalRangeError> {
+            let micros = value.total_microseconds().ok_or(IntervalRangeError)?;
+            let micros = i64::try_from(micros).map_err(|_| IntervalRangeError)?;
+            Ok(chrono::Duration::microseconds(micros))
+        }
+    }
+}
+
+/// An interval can not be converted, since it is out of the range of the target type or is a
+/// year-month interval converted to a fixed length duration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IntervalRangeError;
+
+impl fmt::Display for IntervalRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Interval value out of range")
+    }
+}
+
+impl ::std::error::Error for IntervalRangeError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn duration_round_trip() {
+        let duration = Duration::new(3 * 86_400 + 4_000, 123_456_000);
+        let interval = SqlInterval::from_duration(duration).unwrap();
+        let ds = interval.as_day_second().unwrap();
+        assert_eq!((ds.day, ds.hour, ds.minute, ds.second, ds.fraction), (3, 1, 6, 40, 123_456));
+        assert_eq!(interval.to_duration(), Some(duration));
+    }
+
+    #[test]
+    fn year_month() {
+        let interval = SqlInterval::year_month(SqlIntervalType::SQL_IS_YEAR_TO_MONTH, true, 2, 3);
+        assert_eq!(interval.total_months(), Some(-27));
+        assert_eq!(interval.to_duration(), None);
+        assert!(interval.as_day_second().is_none());
+    }
+
+    #[test]
+    fn column_size() {
+        let interval = SqlInterval::day_second(SqlIntervalType::SQL_IS_DAY_TO_SECOND, false, 0, 0, 0, 0, 0);
+        // "DDDDDDDDD HH:MM:SS.FFFFFF"
+        assert_eq!(interval.column_size(), 25);
+        assert_eq!(interval.decimal_digits(), 6);
+        let interval = SqlInterval::year_month(SqlIntervalType::SQL_IS_YEAR, false, 1, 0);
+        assert_eq!(interval.column_size(), 9);
+    }
+
+    #[test]
+    fn bind_types() {
+        let interval = SqlInterval::year_month(SqlIntervalType::SQL_IS_YEAR_TO_MONTH, false, 1, 2);
+        let c_type = ffi::SQL_C_INTERVAL_YEAR_TO_MONTH as ffi::SQLSMALLINT;
+        assert_eq!(SqlInterval::bind_types(Some(&interval)), (c_type, c_type));
+        let c_type = ffi::SQL_C_INTERVAL_DAY_TO_SECOND as ffi::SQLSMALLINT;
+        assert_eq!(Option::<SqlInterval>::bind_types(Some(&None)), (c_type, c_type));
+    }
+}
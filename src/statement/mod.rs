@@ -3,13 +3,38 @@ mod types;
 mod input;
 mod output;
 mod prepare;
+mod polling;
+mod numeric;
+mod interval;
+mod timestamp_offset;
+mod col_attribute;
+mod params;
+mod named;
+mod cache;
+mod script;
+pub mod escape;
+#[cfg(any(feature = "chrono", feature = "time"))]
+mod datetime;
 pub use self::output::Output;
-use {ffi, safe, Connection, Return, Result, Raii, Handle};
+pub use self::input::Parameter;
+pub use self::numeric::{NumericRangeError, SqlNumeric, MAX_NUMERIC_PRECISION};
+pub use self::interval::{IntervalRangeError, SqlInterval, SqlIntervalType};
+pub use self::col_attribute::{AttributeValue, ColumnAttribute, ColumnAttributes, Searchable, Updatable};
+pub use self::timestamp_offset::{SqlTimestampOffset, TimestampOffsetError};
+pub use self::params::Params;
+pub use self::named::NamedSql;
+pub(crate) use self::cache::StatementCache;
+pub use self::script::{split_script, ScriptError, ScriptOutcome, ScriptStatement};
+pub use self::polling::{ExecuteAsync, FetchAsync};
+use {ffi, safe, Connection, DiagnosticRecord, Encodings, HasEncodings, Return, Result, Raii, Handle, SqlText};
+use warnings::{HasWarnings, Warnings};
 use ffi::SQLRETURN::*;
 use ffi::Nullable;
+use std::cmp;
 use std::marker::PhantomData;
+use std::sync::Arc;
 pub use self::types::OdbcType;
-pub use self::types::{SqlDate, SqlTime, SqlSsTime2, SqlTimestamp, EncodedValue};
+pub use self::types::{SqlDate, SqlTime, SqlSsTime2, SqlTimestamp, SqlGuid, EncodedValue};
 
 // Allocate CHUNK_LEN elements at a time
 const CHUNK_LEN: usize = 64;
@@ -93,6 +118,15 @@ pub struct ColumnDescriptor {
     pub nullable: Option<bool>,
 }
 
+/// Describes a parameter marker of a prepared statement
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParameterDescriptor {
+    pub data_type: ffi::SqlDataType,
+    pub column_size: Option<ffi::SQLULEN>,
+    pub decimal_digits: Option<u16>,
+    pub nullable: Option<bool>,
+}
+
 impl<'a, 'b, S, R, AC: AutocommitMode> Handle for Statement<'a, 'b, S, R, AC> {
     type To = ffi::Stmt;
     unsafe fn handle(&self) -> ffi::SQLHSTMT {
@@ -112,6 +146,41 @@ impl<'a, 'b, S, R, AC: AutocommitMode> Statement<'a, 'b, S, R, AC> {
             encoded_values: Vec::new(),
         }
     }
+
+    /// Takes the warnings reported along with `SQL_SUCCESS_WITH_INFO` by calls on this statement,
+    /// e.g. `01004` if data has been truncated, or informational messages the data source printed
+    /// while executing. Warnings accumulate until taken, up to `MAX_KEPT_WARNINGS`.
+    pub fn take_warnings(&self) -> Vec<DiagnosticRecord> {
+        self.raii.take_warnings()
+    }
+}
+
+impl<'b, AC: AutocommitMode + 'static> Statement<'static, 'b, Allocated, NoResult, AC> {
+    /// Allocates a statement which holds a reference to its connection instead of borrowing it.
+    /// Together with `Environment::connect_shared` this allows for statements which are not bound
+    /// to any lifetime, as long as no borrowed parameters are bound to them. The connection is
+    /// closed once it is no longer referenced by any `Arc` or statement.
+    ///
+    /// # Example
+    /// ```
+    /// # use odbc::*;
+    /// # use std::sync::Arc;
+    /// # fn doc() -> Result<()> {
+    /// let env = Arc::new(create_environment_v3().map_err(|e| e.unwrap())?);
+    /// let conn = Arc::new(Environment::connect_shared(&env, "TestDataSource", "", "")?);
+    /// let stmt = Statement::with_shared_parent(&conn)?;
+    /// let handle = std::thread::spawn(move || -> Result<()> {
+    ///     stmt.exec_direct("SELECT TITLE FROM MOVIES")?;
+    ///     Ok(())
+    /// });
+    /// handle.join().unwrap()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_shared_parent(ds: &Arc<Connection<'static, AC>>) -> Result<Self> {
+        let raii = Raii::with_shared_parent(ds).into_result(&**ds)?;
+        Ok(Self::with_raii(raii))
+    }
 }
 
 impl<'a, 'b, 'env, AC: AutocommitMode> Statement<'a, 'b, Allocated, NoResult, AC> {
@@ -172,6 +241,25 @@ impl<'a, 'b, 'env, AC: AutocommitMode> Statement<'a, 'b, Allocated, NoResult, AC
     }
 }
 
+#[cfg(feature = "async")]
+impl<'a, 'b, AC: AutocommitMode> Statement<'a, 'b, Allocated, NoResult, AC> {
+    /// Like `exec_direct`, but does not consume the statement, so the handle stays valid even if
+    /// the execution fails. Returns `true` if a result set has been created. Callers are
+    /// responsible for tracking the state of the statement.
+    pub(crate) fn exec_direct_in_place(&mut self, statement_text: &str) -> Result<bool> {
+        if self.raii.exec_direct(statement_text).into_result(&*self)? {
+            Ok(self.raii.num_result_cols().into_result(&*self)? > 0)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Changes the type of a statement after `exec_direct_in_place` created a result set
+    pub(crate) fn into_result_set(self) -> Statement<'a, 'b, Executed, HasResult, AC> {
+        Statement::with_raii(self.raii)
+    }
+}
+
 impl<'a, 'b, S, AC: AutocommitMode> Statement<'a, 'b, S, HasResult, AC> {
 
     pub fn affected_row_count(&self) -> Result<ffi::SQLLEN> {
@@ -239,15 +327,19 @@ impl<'a, 'b, S, AC: AutocommitMode> Statement<'a, 'b, S, HasResult, AC> {
 impl<'a, 'b, 'c, S, AC: AutocommitMode> Cursor<'a, 'b, 'c, S, AC> {
     /// Retrieves data for a single column in the result set
     ///
-    /// ## Panics
-    ///
-    /// If you try to convert to `&str` but the data can't be converted
+    /// Character data is decoded according to the `DecodePolicy` of the connection. Fails with a
+    /// `DecodeError` as cause if you try to convert to `&str`, but the data can't be converted
     /// without allocating an intermediate buffer.
     pub fn get_data<'d, T>(&'d mut self, col_or_param_num: u16) -> Result<Option<T>>
     where
         T: Output<'d>,
     {
-        T::get_data(&mut self.stmt.raii, col_or_param_num, &mut self.buffer).into_result(self.stmt)
+        T::get_data(&mut self.stmt.raii, col_or_param_num, &mut self.buffer)
+    }
+
+    /// Takes the warnings reported by the statement so far, see `Statement::take_warnings`
+    pub fn take_warnings(&self) -> Vec<DiagnosticRecord> {
+        self.stmt.take_warnings()
     }
 }
 
@@ -258,7 +350,7 @@ impl<'p> Raii<'p, ffi::Stmt> {
             match ffi::SQLRowCount(self.handle(), &mut count as *mut ffi::SQLLEN) {
                 SQL_SUCCESS => Return::Success(count),
                 SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(count),
-                SQL_ERROR => Return::Error,
+                SQL_ERROR => Return::Error("SQLRowCount"),
                 r => panic!("SQLRowCount returned unexpected result: {:?}", r),
             }
         }
@@ -270,7 +362,7 @@ impl<'p> Raii<'p, ffi::Stmt> {
             match ffi::SQLNumResultCols(self.handle(), &mut num_cols as *mut ffi::SQLSMALLINT) {
                 SQL_SUCCESS => Return::Success(num_cols),
                 SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(num_cols),
-                SQL_ERROR => Return::Error,
+                SQL_ERROR => Return::Error("SQLNumResultCols"),
                 r => panic!("SQLNumResultCols returned unexpected result: {:?}", r),
             }
         }
@@ -278,87 +370,93 @@ impl<'p> Raii<'p, ffi::Stmt> {
 
     fn describe_col(&self, idx: u16) -> Return<ColumnDescriptor> {
         let mut name_buffer: [u8; 512] = [0; 512];
+        let mut wide_name_buffer: [u16; 256] = [0; 256];
         let mut name_length: ffi::SQLSMALLINT = 0;
         let mut data_type: ffi::SqlDataType = ffi::SqlDataType::SQL_UNKNOWN_TYPE;
         let mut column_size: ffi::SQLULEN = 0;
         let mut decimal_digits: ffi::SQLSMALLINT = 0;
         let mut nullable: Nullable = Nullable::SQL_NULLABLE_UNKNOWN;
-        unsafe {
-            match ffi::SQLDescribeCol(
-                self.handle(),
-                idx,
-                name_buffer.as_mut_ptr(),
-                name_buffer.len() as ffi::SQLSMALLINT,
-                &mut name_length as *mut ffi::SQLSMALLINT,
-                &mut data_type as *mut ffi::SqlDataType,
-                &mut column_size as *mut ffi::SQLULEN,
-                &mut decimal_digits as *mut ffi::SQLSMALLINT,
-                &mut nullable as *mut ffi::Nullable,
-            ) {
-                SQL_SUCCESS => Return::Success(ColumnDescriptor {
-                    name: ::environment::DB_ENCODING.decode(&name_buffer[..(name_length as usize)]).0
-                        .to_string(),
-                    data_type: data_type,
-                    column_size: if column_size == 0 {
-                        None
-                    } else {
-                        Some(column_size)
-                    },
-                    decimal_digits: if decimal_digits == 0 {
-                        None
-                    } else {
-                        Some(decimal_digits as u16)
-                    },
-                    nullable: match nullable {
-                        Nullable::SQL_NULLABLE_UNKNOWN => None,
-                        Nullable::SQL_NULLABLE => Some(true),
-                        Nullable::SQL_NO_NULLS => Some(false),
-                    },
-                }),
-                SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(ColumnDescriptor {
-                    name: ::environment::DB_ENCODING.decode(&name_buffer[..(name_length as usize)]).0
-                        .to_string(),
-                    data_type: data_type,
-                    column_size: if column_size == 0 {
-                        None
-                    } else {
-                        Some(column_size)
-                    },
-                    decimal_digits: if decimal_digits == 0 {
-                        None
-                    } else {
-                        Some(decimal_digits as u16)
-                    },
-                    nullable: match nullable {
-                        Nullable::SQL_NULLABLE_UNKNOWN => None,
-                        Nullable::SQL_NULLABLE => Some(true),
-                        Nullable::SQL_NO_NULLS => Some(false),
-                    },
-                }),
-                SQL_ERROR => Return::Error,
-                r => panic!("SQLDescribeCol returned unexpected result: {:?}", r),
+        let wide = self.encodings().wide;
+        let ret = unsafe {
+            if wide {
+                ffi::SQLDescribeColW(
+                    self.handle(),
+                    idx,
+                    wide_name_buffer.as_mut_ptr(),
+                    wide_name_buffer.len() as ffi::SQLSMALLINT,
+                    &mut name_length as *mut ffi::SQLSMALLINT,
+                    &mut data_type as *mut ffi::SqlDataType,
+                    &mut column_size as *mut ffi::SQLULEN,
+                    &mut decimal_digits as *mut ffi::SQLSMALLINT,
+                    &mut nullable as *mut ffi::Nullable,
+                )
+            } else {
+                ffi::SQLDescribeCol(
+                    self.handle(),
+                    idx,
+                    name_buffer.as_mut_ptr(),
+                    name_buffer.len() as ffi::SQLSMALLINT,
+                    &mut name_length as *mut ffi::SQLSMALLINT,
+                    &mut data_type as *mut ffi::SqlDataType,
+                    &mut column_size as *mut ffi::SQLULEN,
+                    &mut decimal_digits as *mut ffi::SQLSMALLINT,
+                    &mut nullable as *mut ffi::Nullable,
+                )
             }
+        };
+        let descriptor = || ColumnDescriptor {
+            name: if wide {
+                let name_length = cmp::min(name_length as usize, wide_name_buffer.len());
+                String::from_utf16_lossy(&wide_name_buffer[..name_length])
+            } else {
+                self.encodings().db.decode(&name_buffer[..(name_length as usize)]).0.to_string()
+            },
+            data_type: data_type,
+            column_size: if column_size == 0 {
+                None
+            } else {
+                Some(column_size)
+            },
+            decimal_digits: if decimal_digits == 0 {
+                None
+            } else {
+                Some(decimal_digits as u16)
+            },
+            nullable: match nullable {
+                Nullable::SQL_NULLABLE_UNKNOWN => None,
+                Nullable::SQL_NULLABLE => Some(true),
+                Nullable::SQL_NO_NULLS => Some(false),
+            },
+        };
+        match ret {
+            SQL_SUCCESS => Return::Success(descriptor()),
+            SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(descriptor()),
+            SQL_ERROR => Return::Error("SQLDescribeCol"),
+            r => panic!("SQLDescribeCol returned unexpected result: {:?}", r),
         }
-
     }
 
     fn exec_direct(&mut self, statement_text: &str) -> Return<bool> {
-        let bytes = unsafe { crate::environment::DB_ENCODING }.encode(statement_text).0;
+        let encodings = self.encodings();
+        let text = SqlText::new(statement_text, encodings.db, encodings.wide);
 
-        let length = bytes.len();
-        if length > ffi::SQLINTEGER::max_value() as usize {
+        let length = text.len();
+        if length > ffi::SQLINTEGER::MAX as usize {
             panic!("Statement text too long");
         }
         match unsafe {
-            ffi::SQLExecDirect(
-                self.handle(),
-                bytes.as_ptr(),
-                length as ffi::SQLINTEGER,
-            )
+            match text {
+                SqlText::Narrow(ref bytes) => {
+                    ffi::SQLExecDirect(self.handle(), bytes.as_ptr(), length as ffi::SQLINTEGER)
+                }
+                SqlText::Wide(ref units) => {
+                    ffi::SQLExecDirectW(self.handle(), units.as_ptr(), length as ffi::SQLINTEGER)
+                }
+            }
         } {
             ffi::SQL_SUCCESS => Return::Success(true),
             ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(true),
-            ffi::SQL_ERROR => Return::Error,
+            ffi::SQL_ERROR => Return::Error("SQLExecDirect"),
             ffi::SQL_NEED_DATA => panic!("SQLExecDirec returned SQL_NEED_DATA"),
             ffi::SQL_NO_DATA => Return::Success(false),
             r => panic!("SQLExecDirect returned unexpected result: {:?}", r),
@@ -379,7 +477,7 @@ impl<'p> Raii<'p, ffi::Stmt> {
         } {
             ffi::SQL_SUCCESS => Return::Success(true),
             ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(true),
-            ffi::SQL_ERROR => Return::Error,
+            ffi::SQL_ERROR => Return::Error("SQLExecDirect"),
             ffi::SQL_NEED_DATA => panic!("SQLExecDirec returned SQL_NEED_DATA"),
             ffi::SQL_NO_DATA => Return::Success(false),
             r => panic!("SQLExecDirect returned unexpected result: {:?}", r),
@@ -391,7 +489,7 @@ impl<'p> Raii<'p, ffi::Stmt> {
         match unsafe { ffi::SQLFetch(self.handle()) } {
             ffi::SQL_SUCCESS => Return::Success(true),
             ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(true),
-            ffi::SQL_ERROR => Return::Error,
+            ffi::SQL_ERROR => Return::Error("SQLFetch"),
             ffi::SQL_NO_DATA => Return::Success(false),
             r => panic!("SQLFetch returned unexpected result: {:?}", r),
         }
@@ -435,7 +533,7 @@ impl<'p> Raii<'p, ffi::Stmt> {
             ) {
                 SQL_SUCCESS => Return::Success(()),
                 SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(()),
-                SQL_ERROR => Return::Error,
+                SQL_ERROR => Return::Error("SQLTables"),
                 r => panic!("SQLTables returned: {:?}", r),
             }
         }
@@ -446,13 +544,25 @@ impl<'p> Raii<'p, ffi::Stmt> {
             match ffi::SQLCloseCursor(self.handle()) {
                 ffi::SQL_SUCCESS => Return::Success(()),
                 ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(()),
-                ffi::SQL_ERROR => Return::Error,
+                ffi::SQL_ERROR => Return::Error("SQLCloseCursor"),
                 r => panic!("unexpected return value from SQLCloseCursor: {:?}", r),
             }
         }
     }
 }
 
+impl<'a, 'b, S, R, AC: AutocommitMode> HasEncodings for Statement<'a, 'b, S, R, AC> {
+    fn encodings(&self) -> Encodings {
+        self.raii.encodings()
+    }
+}
+
+impl<'a, 'b, S, R, AC: AutocommitMode> HasWarnings for Statement<'a, 'b, S, R, AC> {
+    fn warnings(&self) -> Option<&Warnings> {
+        self.raii.warnings()
+    }
+}
+
 unsafe impl<'con, 'param, C, P, AC: AutocommitMode> safe::Handle for Statement<'con, 'param, C, P, AC> {
 
     const HANDLE_TYPE : ffi::HandleType = ffi::SQL_HANDLE_STMT;
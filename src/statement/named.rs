@@ -0,0 +1,208 @@
+//! Named `:name` placeholders, rewritten to positional `?` markers
+use super::types::OdbcType;
+use odbc_safe::AutocommitMode;
+use std::collections::HashMap;
+use {DiagnosticRecord, Result, Statement};
+
+/// SQL text with named placeholders like `:customer_id`, rewritten to the positional `?` markers
+/// understood by ODBC.
+///
+/// Placeholders inside string literals, quoted identifiers (`"..."`, `[...]` and `` `...` ``) and
+/// comments are left untouched, as are PostgreSQL style `::` casts. A name may appear several
+/// times, each occurrence becomes a marker of its own.
+///
+/// # Example
+/// ```
+/// # use odbc::*;
+/// let named = NamedSql::parse("SELECT * FROM T WHERE A = :a OR B = :b OR C = :a");
+/// assert_eq!(named.sql(), "SELECT * FROM T WHERE A = ? OR B = ? OR C = ?");
+/// assert_eq!(named.indices("a"), Some(&[1, 3][..]));
+/// assert_eq!(named.indices("b"), Some(&[2][..]));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NamedSql {
+    sql: String,
+    /// Names in order of their first occurrence
+    names: Vec<String>,
+    indices: HashMap<String, Vec<u16>>,
+}
+
+impl NamedSql {
+    /// Rewrites the named placeholders in `sql` to `?` markers
+    pub fn parse(sql: &str) -> NamedSql {
+        let mut named = NamedSql {
+            sql: String::with_capacity(sql.len()),
+            names: Vec::new(),
+            indices: HashMap::new(),
+        };
+        let mut num_markers = 0u16;
+        let mut chars = sql.char_indices().peekable();
+        while let Some((_, c)) = chars.next() {
+            match c {
+                '\'' | '"' | '`' | '[' => {
+                    let end = match c {
+                        '[' => ']',
+                        c => c,
+                    };
+                    named.sql.push(c);
+                    // A doubled delimiter ends the literal and starts a new one, so it needs no special care
+                    for (_, c) in chars.by_ref() {
+                        named.sql.push(c);
+                        if c == end {
+                            break;
+                        }
+                    }
+                }
+                '-' if chars.peek().map(|&(_, c)| c) == Some('-') => {
+                    named.sql.push(c);
+                    for (_, c) in chars.by_ref() {
+                        named.sql.push(c);
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                '/' if chars.peek().map(|&(_, c)| c) == Some('*') => {
+                    named.sql.push(c);
+                    named.sql.push(chars.next().unwrap().1);
+                    let mut previous = ' ';
+                    for (_, c) in chars.by_ref() {
+                        named.sql.push(c);
+                        if previous == '*' && c == '/' {
+                            break;
+                        }
+                        previous = c;
+                    }
+                }
+                '?' => {
+                    named.sql.push(c);
+                    num_markers += 1;
+                }
+                ':' => match chars.peek().cloned() {
+                    Some((_, ':')) => {
+                        named.sql.push_str("::");
+                        chars.next();
+                    }
+                    Some((name_start, n)) if n.is_alphabetic() || n == '_' => {
+                        let mut name_end = sql.len();
+                        while let Some(&(i, n)) = chars.peek() {
+                            if !(n.is_alphanumeric() || n == '_') {
+                                name_end = i;
+                                break;
+                            }
+                            chars.next();
+                        }
+                        num_markers += 1;
+                        named.add(&sql[name_start..name_end], num_markers);
+                        named.sql.push('?');
+                    }
+                    _ => named.sql.push(c),
+                },
+                _ => named.sql.push(c),
+            }
+        }
+        named
+    }
+
+    fn add(&mut self, name: &str, index: u16) {
+        if !self.indices.contains_key(name) {
+            self.names.push(name.to_owned());
+        }
+        self.indices.entry(name.to_owned()).or_default().push(index);
+    }
+
+    /// SQL text with positional `?` markers, to be passed to `prepare` or `exec_direct`
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Parameter names in order of their first occurrence
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Indices of the markers replacing the placeholder `name`, starting at `1`. `None` if the
+    /// text has no such placeholder.
+    pub fn indices(&self, name: &str) -> Option<&[u16]> {
+        self.indices.get(name).map(|indices| &indices[..])
+    }
+}
+
+impl<'a, 'b, S, R, AC: AutocommitMode> Statement<'a, 'b, S, R, AC> {
+    /// Binds `value` to every marker replacing the placeholder `name` of `named`.
+    ///
+    /// Fails with state `07009` if `named` has no placeholder called `name`.
+    ///
+    /// # Example
+    /// ```
+    /// # use odbc::*;
+    /// # fn doc() -> Result<()> {
+    /// let env = create_environment_v3().map_err(|e| e.unwrap())?;
+    /// let conn = env.connect("TestDataSource", "", "")?;
+    /// let named = NamedSql::parse("SELECT TITLE FROM MOVIES WHERE YEAR = :year");
+    /// let stmt = Statement::with_parent(&conn)?;
+    /// let year = 1968;
+    /// let stmt = stmt.bind_named(&named, "year", &year)?;
+    /// if let Data(mut stmt) = stmt.exec_direct(named.sql())? {
+    ///     // ...
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bind_named<'c, T>(
+        self,
+        named: &NamedSql,
+        name: &str,
+        value: &'c T,
+    ) -> Result<Statement<'a, 'c, S, R, AC>>
+    where
+        T: OdbcType<'c>,
+        'b: 'c,
+    {
+        let indices = named.indices(name).ok_or_else(|| {
+            DiagnosticRecord::with_message(b"07009", &format!("Unknown parameter name: {}", name))
+        })?;
+        let mut stmt: Statement<'a, 'c, S, R, AC> = self;
+        for &index in indices {
+            stmt = stmt.bind_parameter(index, value)?;
+        }
+        Ok(stmt)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn repeated_names() {
+        let named = NamedSql::parse("SELECT :b, :a, :b");
+        assert_eq!(named.sql(), "SELECT ?, ?, ?");
+        assert_eq!(named.names(), &["b".to_owned(), "a".to_owned()][..]);
+        assert_eq!(named.indices("b"), Some(&[1, 3][..]));
+        assert_eq!(named.indices("a"), Some(&[2][..]));
+        assert_eq!(named.indices("c"), None);
+    }
+
+    #[test]
+    fn skip_literals_identifiers_and_comments() {
+        let sql = "SELECT ':a', 'it''s :b', \":c\", [:d], `:e` -- :f\n/* :g */ FROM T WHERE X = :x";
+        let named = NamedSql::parse(sql);
+        assert_eq!(named.sql(), &sql.replace(":x", "?")[..]);
+        assert_eq!(named.names(), &["x".to_owned()][..]);
+    }
+
+    #[test]
+    fn positional_markers_and_casts() {
+        let named = NamedSql::parse("SELECT ?::text, :name::int, ':', :1");
+        assert_eq!(named.sql(), "SELECT ?::text, ?::int, ':', :1");
+        assert_eq!(named.indices("name"), Some(&[2][..]));
+    }
+
+    #[test]
+    fn unicode_names() {
+        let named = NamedSql::parse("SELECT 'ä' WHERE ö = :größe");
+        assert_eq!(named.sql(), "SELECT 'ä' WHERE ö = ?");
+        assert_eq!(named.indices("größe"), Some(&[1][..]));
+    }
+}
@@ -0,0 +1,334 @@
+//! Exact numeric values exchanged as `SQL_NUMERIC_STRUCT`
+//!
+//! Drivers only honor precision and scale of `SQL_C_NUMERIC` values if they are set in the
+//! application descriptors, so binding a `SqlNumeric` sets the fields of the APD record and
+//! fetching one sets the fields of the ARD record to the precision and scale of the column.
+use super::types::{EncodedValue, OdbcType};
+use diagnostics::Decoded;
+use std::fmt;
+use std::mem::size_of;
+use std::ptr::{null_mut, read_unaligned};
+use {ffi, Handle, HasEncodings, Raii, Result, Return};
+
+/// Largest precision of a `SqlNumeric` which fits into its 128 bit mantissa
+pub const MAX_NUMERIC_PRECISION: u8 = 38;
+
+/// Exact numeric value, e.g. of a `DECIMAL` or `NUMERIC` column, with the same layout as
+/// `SQL_NUMERIC_STRUCT`
+///
+/// The value is `mantissa * 10^-scale`.
+///
+/// # Example
+/// ```
+/// # use odbc::*;
+/// let price = SqlNumeric::new(-12345, 10, 2);
+/// assert_eq!(price.to_string(), "-123.45");
+/// assert_eq!(price.mantissa(), Some(-12345));
+/// ```
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SqlNumeric {
+    /// Number of significant decimal digits
+    pub precision: u8,
+    /// Number of digits right of the decimal point. Negative values multiply by powers of ten.
+    pub scale: i8,
+    /// `1` for positive values and zero, `0` for negative values
+    pub sign: u8,
+    /// Magnitude of the mantissa as little endian unsigned integer
+    pub val: [u8; 16],
+}
+
+impl SqlNumeric {
+    /// Creates a numeric value of `mantissa * 10^-scale`
+    pub fn new(mantissa: i128, precision: u8, scale: i8) -> SqlNumeric {
+        SqlNumeric::from_magnitude(mantissa < 0, mantissa.unsigned_abs(), precision, scale)
+    }
+
+    /// Creates a numeric value from the magnitude of its mantissa and its sign
+    pub fn from_magnitude(negative: bool, magnitude: u128, precision: u8, scale: i8) -> SqlNumeric {
+        SqlNumeric {
+            precision,
+            scale,
+            sign: if negative && magnitude != 0 { 0 } else { 1 },
+            val: magnitude.to_le_bytes(),
+        }
+    }
+
+    /// `true` if the value is less than zero
+    pub fn is_negative(&self) -> bool {
+        self.sign == 0
+    }
+
+    /// Magnitude of the mantissa
+    pub fn magnitude(&self) -> u128 {
+        u128::from_le_bytes(self.val)
+    }
+
+    /// The signed mantissa, or `None` if it does not fit into an `i128`
+    pub fn mantissa(&self) -> Option<i128> {
+        let magnitude = self.magnitude();
+        if self.is_negative() {
+            if magnitude == i128::MIN.unsigned_abs() {
+                Some(i128::MIN)
+            } else if magnitude < i128::MIN.unsigned_abs() {
+                Some(-(magnitude as i128))
+            } else {
+                None
+            }
+        } else if magnitude <= i128::MAX as u128 {
+            Some(magnitude as i128)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for SqlNumeric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut digits = self.magnitude().to_string();
+        if self.scale < 0 {
+            digits.extend((0..-(self.scale as i32)).map(|_| '0'));
+        } else if self.scale > 0 {
+            let scale = self.scale as usize;
+            if digits.len() <= scale {
+                let zeros: String = (0..(scale + 1 - digits.len())).map(|_| '0').collect();
+                digits.insert_str(0, &zeros);
+            }
+            let point = digits.len() - scale;
+            digits.insert(point, '.');
+        }
+        if self.is_negative() {
+            digits.insert(0, '-');
+        }
+        f.pad(&digits)
+    }
+}
+
+unsafe impl<'a> OdbcType<'a> for SqlNumeric {
+    fn sql_data_type() -> ffi::SqlDataType {
+        ffi::SQL_NUMERIC
+    }
+    fn c_data_type() -> ffi::SqlCDataType {
+        ffi::SQL_C_NUMERIC
+    }
+
+    fn convert(buffer: &'a [u8]) -> Self {
+        assert_eq!(buffer.len(), size_of::<Self>());
+        unsafe { read_unaligned(buffer.as_ptr() as *const Self) }
+    }
+
+    fn column_size(&self) -> ffi::SQLULEN {
+        self.precision as ffi::SQLULEN
+    }
+
+    fn decimal_digits(&self) -> ffi::SQLSMALLINT {
+        self.scale as ffi::SQLSMALLINT
+    }
+
+    fn value_ptr(&self) -> ffi::SQLPOINTER {
+        self as *const Self as ffi::SQLPOINTER
+    }
+
+    fn bind_descriptor(&self, stmt: &mut Raii<ffi::Stmt>, parameter_index: u16) -> Result<()> {
+        stmt.set_numeric_parameter(
+            parameter_index,
+            self.column_size(),
+            self.decimal_digits(),
+            self.value_ptr(),
+        )
+    }
+
+    fn get_data_target_type(stmt: &mut Raii<ffi::Stmt>, col: u16) -> Result<ffi::SQLSMALLINT> {
+        stmt.set_numeric_target(col)?;
+        Ok(ffi::SQL_ARD_TYPE)
+    }
+
+    fn encoded_value(&self) -> EncodedValue {
+        EncodedValue::new(None)
+    }
+}
+
+impl<'p> Raii<'p, ffi::Stmt> {
+    /// Sets type, precision and scale of the APD record of a parameter bound to a `SQL_C_NUMERIC`
+    /// value. The data pointer is set last, since setting the other fields unbinds the record.
+    fn set_numeric_parameter(
+        &mut self,
+        parameter_index: u16,
+        precision: ffi::SQLULEN,
+        scale: ffi::SQLSMALLINT,
+        value_ptr: ffi::SQLPOINTER,
+    ) -> Result<()> {
+        let apd = self.app_descriptor(ffi::SQL_ATTR_APP_PARAM_DESC)?;
+        self.set_numeric_record(apd, parameter_index, precision as ffi::SQLSMALLINT, scale)?;
+        self.set_desc_field(apd, parameter_index, ffi::SQL_DESC_DATA_PTR, value_ptr)
+    }
+
+    /// Prepares the ARD record of a column, so `SQLGetData` with `SQL_ARD_TYPE` retrieves it as
+    /// `SQL_C_NUMERIC` with the precision and scale of the column.
+    fn set_numeric_target(&mut self, col: u16) -> Result<()> {
+        let column = self.describe_col(col).into_result(self)?;
+        let precision = column
+            .column_size
+            .map_or(MAX_NUMERIC_PRECISION as ffi::SQLSMALLINT, |size| {
+                size.min(MAX_NUMERIC_PRECISION as ffi::SQLULEN) as ffi::SQLSMALLINT
+            });
+        let scale = column.decimal_digits.unwrap_or(0) as ffi::SQLSMALLINT;
+        let ard = self.app_descriptor(ffi::SQL_ATTR_APP_ROW_DESC)?;
+        self.set_numeric_record(ard, col, precision, scale)
+    }
+
+    fn set_numeric_record(
+        &self,
+        desc: ffi::SQLHDESC,
+        record: u16,
+        precision: ffi::SQLSMALLINT,
+        scale: ffi::SQLSMALLINT,
+    ) -> Result<()> {
+        let c_type = ffi::SQL_C_NUMERIC as ffi::SQLSMALLINT;
+        self.set_desc_field(desc, record, ffi::SQL_DESC_TYPE, c_type as isize as ffi::SQLPOINTER)?;
+        self.set_desc_field(desc, record, ffi::SQL_DESC_PRECISION, precision as isize as ffi::SQLPOINTER)?;
+        self.set_desc_field(desc, record, ffi::SQL_DESC_SCALE, scale as isize as ffi::SQLPOINTER)
+    }
+
+    /// Handle of the implicitly allocated application descriptor stored in `attribute`
+    pub(super) fn app_descriptor(&self, attribute: ffi::SQLINTEGER) -> Result<ffi::SQLHDESC> {
+        let mut desc: ffi::SQLHDESC = null_mut();
+        let ret = match unsafe {
+            ffi::SQLGetStmtAttr(
+                self.handle(),
+                attribute,
+                &mut desc as *mut ffi::SQLHDESC as ffi::SQLPOINTER,
+                ffi::SQL_IS_POINTER as ffi::SQLINTEGER,
+                null_mut(),
+            )
+        } {
+            ffi::SQL_SUCCESS => Return::Success(desc),
+            ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(desc),
+            ffi::SQL_ERROR => Return::Error("SQLGetStmtAttr"),
+            r => panic!("SQLGetStmtAttr returned unexpected result: {:?}", r),
+        };
+        ret.into_result(self)
+    }
+
+    pub(super) fn set_desc_field(
+        &self,
+        desc: ffi::SQLHDESC,
+        record: u16,
+        field: ffi::SQLSMALLINT,
+        value: ffi::SQLPOINTER,
+    ) -> Result<()> {
+        let ret = match unsafe {
+            ffi::SQLSetDescField(desc, record as ffi::SQLSMALLINT, field, value, 0)
+        } {
+            ffi::SQL_SUCCESS => Return::Success(()),
+            ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(()),
+            ffi::SQL_ERROR => Return::Error("SQLSetDescField"),
+            r => panic!("SQLSetDescField returned unexpected result: {:?}", r),
+        };
+        // Diagnostics are posted on the descriptor, not the statement
+        ret.into_result(&Decoded::new(desc, self.encodings()))
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+mod rust_decimal_conversions {
+    use super::{NumericRangeError, SqlNumeric, MAX_NUMERIC_PRECISION};
+    use rust_decimal::Decimal;
+    use std::convert::TryFrom;
+
+    impl From<Decimal> for SqlNumeric {
+        fn from(value: Decimal) -> SqlNumeric {
+            // The mantissa of a `Decimal` has 96 bits, i.e. at most 29 digits and its scale is at
+            // most 28, so this is always exact.
+            SqlNumeric::new(value.mantissa(), MAX_NUMERIC_PRECISION, value.scale() as i8)
+        }
+    }
+
+    impl TryFrom<SqlNumeric> for Decimal {
+        type Error = NumericRangeError;
+
+        fn try_from(value: SqlNumeric) -> Result<Decimal, NumericRangeError> {
+            let mantissa = value.mantissa().ok_or(NumericRangeError)?;
+            if value.scale < 0 {
+                // Multiply the mantissa instead, since `Decimal` does not support negative scales
+                let factor = 10i128.checked_pow(-(value.scale as i32) as u32).ok_or(NumericRangeError)?;
+                let mantissa = mantissa.checked_mul(factor).ok_or(NumericRangeError)?;
+                Decimal::try_from_i128_with_scale(mantissa, 0).map_err(|_| NumericRangeError)
+            } else {
+                Decimal::try_from_i128_with_scale(mantissa, value.scale as u32)
+                    .map_err(|_| NumericRangeError)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "bigdecimal")]
+mod bigdecimal_conversions {
+    use super::{NumericRangeError, SqlNumeric, MAX_NUMERIC_PRECISION};
+    use bigdecimal::num_bigint::{BigInt, Sign};
+    use bigdecimal::BigDecimal;
+    use std::convert::TryFrom;
+
+    impl From<SqlNumeric> for BigDecimal {
+        fn from(value: SqlNumeric) -> BigDecimal {
+            let sign = if value.is_negative() { Sign::Minus } else { Sign::Plus };
+            let mantissa = BigInt::from_bytes_le(sign, &value.val);
+            BigDecimal::new(mantissa, value.scale as i64)
+        }
+    }
+
+    impl TryFrom<BigDecimal> for SqlNumeric {
+        type Error = NumericRangeError;
+
+        fn try_from(value: BigDecimal) -> Result<SqlNumeric, NumericRangeError> {
+            let (mantissa, scale) = value.into_bigint_and_exponent();
+            let scale = i8::try_from(scale).map_err(|_| NumericRangeError)?;
+            let (sign, bytes) = mantissa.to_bytes_le();
+            if bytes.len() > 16 {
+                return Err(NumericRangeError);
+            }
+            let mut val = [0u8; 16];
+            val[..bytes.len()].copy_from_slice(&bytes);
+            let magnitude = u128::from_le_bytes(val);
+            Ok(SqlNumeric::from_magnitude(sign == Sign::Minus, magnitude, MAX_NUMERIC_PRECISION, scale))
+        }
+    }
+}
+
+/// A numeric value is out of the range of the type it is converted to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumericRangeError;
+
+impl fmt::Display for NumericRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Numeric value out of range")
+    }
+}
+
+impl ::std::error::Error for NumericRangeError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(SqlNumeric::new(12345, 10, 2).to_string(), "123.45");
+        assert_eq!(SqlNumeric::new(-5, 10, 3).to_string(), "-0.005");
+        assert_eq!(SqlNumeric::new(42, 10, -2).to_string(), "4200");
+        assert_eq!(SqlNumeric::new(0, 10, 0).to_string(), "0");
+    }
+
+    #[test]
+    fn mantissa_round_trip() {
+        for &mantissa in &[0, 1, -1, i128::MAX, i128::MIN] {
+            assert_eq!(SqlNumeric::new(mantissa, 38, 0).mantissa(), Some(mantissa));
+        }
+        assert_eq!(SqlNumeric::from_magnitude(false, u128::MAX, 38, 0).mantissa(), None);
+    }
+
+    #[test]
+    fn layout_matches_sql_numeric_struct() {
+        assert_eq!(size_of::<SqlNumeric>(), 19);
+    }
+}
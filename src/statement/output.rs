@@ -1,5 +1,5 @@
 use raii::Raii;
-use {ffi, Handle, Return};
+use {ffi, Handle, HasEncodings, Result, Return};
 use super::types::OdbcType;
 
 /// Indicates that a type can be retrieved using `Cursor::get_data`
@@ -8,7 +8,7 @@ pub unsafe trait Output<'a>: Sized {
         stmt: &mut Raii<ffi::Stmt>,
         col_or_param_num: u16,
         buffer: &'a mut Vec<u8>,
-    ) -> Return<Option<Self>>;
+    ) -> Result<Option<Self>>;
 }
 
 unsafe impl<'a, T> Output<'a> for T
@@ -19,29 +19,37 @@ where
         stmt: &mut Raii<ffi::Stmt>,
         col_or_param_num: u16,
         buffer: &'a mut Vec<u8>,
-    ) -> Return<Option<Self>> {
-        stmt.get_data(col_or_param_num, buffer)
+    ) -> Result<Option<Self>> {
+        let encodings = stmt.encodings();
+        let target_type = T::get_data_target_type(stmt, col_or_param_num)?;
+        match stmt.get_data::<T>(col_or_param_num, target_type, buffer).into_result(stmt)? {
+            Some(data) => Ok(Some(T::convert_with_encoding(data, encodings.db, encodings.decode)?)),
+            None => Ok(None),
+        }
     }
 }
 
 impl<'p> Raii<'p, ffi::Stmt> {
+    /// Retrieves the data of a column, without converting it into `T`
     fn get_data<'a, T>(
         &mut self,
         col_or_param_num: u16,
+        target_type: ffi::SQLSMALLINT,
         buffer: &'a mut Vec<u8>
-    ) -> Return<Option<T>>
+    ) -> Return<Option<&'a [u8]>>
     where
         T: OdbcType<'a>,
     {
-        self.get_partial_data(col_or_param_num, buffer, 0)
+        self.get_partial_data::<T>(col_or_param_num, target_type, buffer, 0)
     }
 
     fn get_partial_data<'a, T>(
         &mut self,
         col_or_param_num: u16,
+        target_type: ffi::SQLSMALLINT,
         buffer: &'a mut Vec<u8>,
         start_pos: usize
-    ) -> Return<Option<T>>
+    ) -> Return<Option<&'a [u8]>>
     where
         T: OdbcType<'a>,
     {
@@ -53,10 +61,10 @@ impl<'p> Raii<'p, ffi::Stmt> {
         }
         let mut indicator: ffi::SQLLEN = 0;
         // Get buffer length...
-        let result = unsafe { ffi::SQLGetData(
+        let result = unsafe { ffi::SQLGetDataWithTargetType(
                 self.handle(),
                 col_or_param_num,
-                T::c_data_type(),
+                target_type,
                 buffer.as_mut_ptr().offset(start_pos as isize) as ffi::SQLPOINTER,
                 (buffer.len() - start_pos) as ffi::SQLLEN,
                 &mut indicator as *mut ffi::SQLLEN,
@@ -67,8 +75,7 @@ impl<'p> Raii<'p, ffi::Stmt> {
                     Return::Success(None)
                 } else {
                     assert!(start_pos + indicator as usize <= buffer.len(), "no more data but indicatior outside of data buffer");
-                    let slice = &buffer[..(start_pos + indicator as usize)];
-                    Return::Success(Some(T::convert(slice)))
+                    Return::Success(Some(&buffer[..(start_pos + indicator as usize)]))
                 }
             }
             ffi::SQL_SUCCESS_WITH_INFO => {
@@ -83,20 +90,19 @@ impl<'p> Raii<'p, ffi::Stmt> {
                 let null_offset = T::null_bytes_count();
                 if indicator == ffi::SQL_NO_TOTAL {
                     buffer.resize(initial_len * 2, 0);
-                    return self.get_partial_data(col_or_param_num, buffer, initial_len - null_offset);
+                    return self.get_partial_data::<T>(col_or_param_num, target_type, buffer, initial_len - null_offset);
                 } else {
                     // Check if string has been truncated.
                     if indicator >= initial_len as ffi::SQLLEN {
                         buffer.resize(indicator as usize + T::null_bytes_count(), 0);
-                        return self.get_partial_data(col_or_param_num, buffer, initial_len - null_offset);
+                        return self.get_partial_data::<T>(col_or_param_num, target_type, buffer, initial_len - null_offset);
                     } else {
-                        let slice = &buffer[..(start_pos + indicator as usize)];
                         // No truncation. Warning may be due to some other issue.
-                        Return::SuccessWithInfo(Some(T::convert(slice)))
+                        Return::SuccessWithInfo(Some(&buffer[..(start_pos + indicator as usize)]))
                     }
                 }
             }
-            ffi::SQL_ERROR => Return::Error,
+            ffi::SQL_ERROR => Return::Error("SQLGetData"),
             ffi::SQL_NO_DATA => panic!("SQLGetData has already returned the colmun data"),
             r => panic!("unexpected return value from SQLGetData: {:?}", r),
         }
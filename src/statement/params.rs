@@ -0,0 +1,147 @@
+//! Binding several parameters at once
+use super::{NoResult, Prepared, ResultSetState, Statement};
+use odbc_safe::AutocommitMode;
+use {DiagnosticRecord, Result};
+
+/// A set of values, which can be bound to the parameter markers of a statement in one call
+///
+/// Implemented for tuples of up to twelve `OdbcType` values, binding the first element to the
+/// first marker. Use `impl_params!` to implement it for a struct.
+pub trait Params<'c> {
+    /// Number of parameters bound by `bind_to`
+    fn param_count(&self) -> u16;
+
+    /// Binds the values to the parameter markers `1` to `param_count`
+    fn bind_to<'a, 'b, S, R, AC: AutocommitMode>(
+        &'c self,
+        stmt: Statement<'a, 'b, S, R, AC>,
+    ) -> Result<Statement<'a, 'c, S, R, AC>>
+    where
+        'b: 'c;
+}
+
+/// Implements `Params` for a struct, binding the listed fields in order
+///
+/// # Example
+/// ```
+/// # #[macro_use] extern crate odbc;
+/// struct Movie {
+///     title: String,
+///     year: i32,
+/// }
+/// impl_params!(Movie { title, year });
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! impl_params {
+    ($t:ty { $($field:ident),+ $(,)* }) => {
+        impl<'c> $crate::Params<'c> for $t {
+            fn param_count(&self) -> u16 {
+                [$(stringify!($field)),+].len() as u16
+            }
+
+            fn bind_to<'a, 'b, S, R, AC: $crate::safe::AutocommitMode>(
+                &'c self,
+                stmt: $crate::Statement<'a, 'b, S, R, AC>,
+            ) -> $crate::Result<$crate::Statement<'a, 'c, S, R, AC>>
+            where
+                'b: 'c,
+            {
+                let mut index = 0u16;
+                $(
+                    index += 1;
+                    let stmt = stmt.bind_parameter(index, &self.$field)?;
+                )+
+                Ok(stmt)
+            }
+        }
+    };
+}
+
+macro_rules! impl_tuple_params {
+    ($($t:ident $idx:tt),+) => {
+        impl<'c, $($t),+> Params<'c> for ($($t,)+)
+        where
+            $($t: ::OdbcType<'c>),+
+        {
+            fn param_count(&self) -> u16 {
+                [$(stringify!($t)),+].len() as u16
+            }
+
+            fn bind_to<'a, 'b, S, R, AC: AutocommitMode>(
+                &'c self,
+                stmt: Statement<'a, 'b, S, R, AC>,
+            ) -> Result<Statement<'a, 'c, S, R, AC>>
+            where
+                'b: 'c,
+            {
+                let mut index = 0u16;
+                $(
+                    index += 1;
+                    let stmt = stmt.bind_parameter(index, &self.$idx)?;
+                )+
+                Ok(stmt)
+            }
+        }
+    };
+}
+
+impl_tuple_params!(A 0);
+impl_tuple_params!(A 0, B 1);
+impl_tuple_params!(A 0, B 1, C 2);
+impl_tuple_params!(A 0, B 1, C 2, D 3);
+impl_tuple_params!(A 0, B 1, C 2, D 3, E 4);
+impl_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+impl_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+impl_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+impl<'a, 'b, AC: AutocommitMode> Statement<'a, 'b, Prepared, NoResult, AC> {
+    /// Binds all values of `params` to the parameter markers of the statement
+    ///
+    /// Fails with state `07002` if the number of values differs from the number of parameter
+    /// markers.
+    ///
+    /// # Example
+    /// ```
+    /// # use odbc::*;
+    /// # fn doc() -> Result<()> {
+    /// let env = create_environment_v3().map_err(|e| e.unwrap())?;
+    /// let conn = env.connect("TestDataSource", "", "")?;
+    /// let stmt = Statement::with_parent(&conn)?;
+    /// let stmt = stmt.prepare("SELECT TITLE FROM MOVIES WHERE YEAR > ? AND YEAR < ?")?;
+    /// let stmt = stmt.bind_params(&(1960, 2000))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bind_params<'c, P>(self, params: &'c P) -> Result<Statement<'a, 'c, Prepared, NoResult, AC>>
+    where
+        P: Params<'c>,
+        'b: 'c,
+    {
+        let num_params = self.num_params()?;
+        if num_params != params.param_count() as i16 {
+            return Err(DiagnosticRecord::with_message(
+                b"07002",
+                &format!(
+                    "Statement has {} parameter markers, but {} values have been supplied",
+                    num_params,
+                    params.param_count()
+                ),
+            ).into());
+        }
+        params.bind_to(self)
+    }
+
+    /// Binds all values of `params` using `bind_params` and executes the statement
+    pub fn execute_with<'c, P>(self, params: &'c P) -> Result<ResultSetState<'a, 'c, Prepared, AC>>
+    where
+        P: Params<'c>,
+        'b: 'c,
+    {
+        self.bind_params(params)?.execute()
+    }
+}
@@ -0,0 +1,393 @@
+//! Native asynchronous execution, using the asynchronous mode of the driver
+//!
+//! With `SQL_ATTR_ASYNC_ENABLE` set, a driver returns `SQL_STILL_EXECUTING` instead of blocking
+//! the calling thread. The futures in this module call the function again each time they are
+//! polled, until the driver reports completion. Since ODBC does not notify the application when
+//! it is done, a pending future is woken again after a delay, which grows from one up to fifty
+//! milliseconds the longer the operation takes. This trades a little latency for not keeping a
+//! core busy while waiting.
+//!
+//! Dropping a pending future cancels the operation and blocks the dropping thread until the driver
+//! acknowledged the cancellation, polling it with the same delays.
+//!
+//! Asynchronous mode is only enabled for the duration of a single operation, so the statement can
+//! be used with the blocking API in between. Drivers which do not support asynchronous execution
+//! block inside `poll` instead.
+use super::*;
+use backoff::Backoff;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+// Values of `SQL_ATTR_ASYNC_ENABLE` not exposed by odbc-sys
+pub(super) const SQL_ASYNC_ENABLE_OFF: usize = 0;
+const SQL_ASYNC_ENABLE_ON: usize = 1;
+
+enum Operation {
+    ExecDirect(SqlText),
+    Execute,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Start,
+    Executing,
+    Describing,
+}
+
+/// Future returned by `Statement::exec_direct_async` and `Statement::execute_async`
+///
+/// Resolves to the same result as the blocking `exec_direct` or `execute` would. Dropping it
+/// before completion cancels the execution.
+pub struct ExecuteAsync<'a, 'b, S, AC: AutocommitMode> {
+    stmt: Option<Statement<'a, 'b, S, NoResult, AC>>,
+    operation: Operation,
+    phase: Phase,
+    async_enabled: bool,
+    // `true` while the driver is still executing an asynchronous call
+    pending: bool,
+    backoff: Backoff,
+}
+
+/// Future returned by `Statement::fetch_async`
+///
+/// Resolves to the same result as the blocking `fetch` would. Dropping it before completion
+/// cancels the fetch.
+pub struct FetchAsync<'s, 'a: 's, 'b: 's, S: 's, AC: AutocommitMode + 's> {
+    stmt: Option<&'s mut Statement<'a, 'b, S, HasResult, AC>>,
+    started: bool,
+    async_enabled: bool,
+    pending: bool,
+    backoff: Backoff,
+}
+
+impl<'a, 'b, AC: AutocommitMode> Statement<'a, 'b, Allocated, NoResult, AC> {
+    /// Like `exec_direct`, but executes the statement using the asynchronous mode of the driver.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate odbc;
+    /// # extern crate futures_executor;
+    /// # use odbc::*;
+    /// # fn main() -> Result<()> {
+    /// let env = create_environment_v3().map_err(|e| e.unwrap())?;
+    /// let conn = env.connect("TestDataSource", "", "")?;
+    /// let stmt = Statement::with_parent(&conn)?;
+    /// let query = stmt.exec_direct_async("SELECT TITLE FROM MOVIES ORDER BY YEAR");
+    /// if let Data(mut stmt) = futures_executor::block_on(query)? {
+    ///     let mut cursor = futures_executor::block_on(stmt.fetch_async())?.unwrap();
+    ///     assert_eq!(cursor.get_data::<String>(1)?.unwrap(), "2001: A Space Odyssey");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn exec_direct_async(self, statement_text: &str) -> ExecuteAsync<'a, 'b, Allocated, AC> {
+        let encodings = self.raii.encodings();
+        let text = SqlText::new(statement_text, encodings.db, encodings.wide);
+        ExecuteAsync::new(self, Operation::ExecDirect(text))
+    }
+}
+
+impl<'a, 'b, AC: AutocommitMode> Statement<'a, 'b, Prepared, NoResult, AC> {
+    /// Like `execute`, but executes the statement using the asynchronous mode of the driver.
+    pub fn execute_async(self) -> ExecuteAsync<'a, 'b, Prepared, AC> {
+        ExecuteAsync::new(self, Operation::Execute)
+    }
+}
+
+impl<'a, 'b, S, AC: AutocommitMode> Statement<'a, 'b, S, HasResult, AC> {
+    /// Like `fetch`, but fetches the next row using the asynchronous mode of the driver.
+    pub fn fetch_async<'s>(&'s mut self) -> FetchAsync<'s, 'a, 'b, S, AC> {
+        FetchAsync {
+            stmt: Some(self),
+            started: false,
+            async_enabled: false,
+            pending: false,
+            backoff: Backoff::new(),
+        }
+    }
+}
+
+impl<'a, 'b, S, AC: AutocommitMode> ExecuteAsync<'a, 'b, S, AC> {
+    fn new(stmt: Statement<'a, 'b, S, NoResult, AC>, operation: Operation) -> Self {
+        ExecuteAsync {
+            stmt: Some(stmt),
+            operation,
+            phase: Phase::Start,
+            async_enabled: false,
+            pending: false,
+            backoff: Backoff::new(),
+        }
+    }
+
+    fn poll_operation(&mut self) -> Poll<Return<bool>> {
+        let stmt = self.stmt.as_mut().expect("future polled after completion");
+        match self.operation {
+            Operation::ExecDirect(ref text) => stmt.raii.poll_exec_direct(text),
+            Operation::Execute => stmt.raii.poll_execute(),
+        }
+    }
+
+    fn finish(&mut self, has_result: bool) -> Result<ResultSetState<'a, 'b, S, AC>> {
+        let mut stmt = self.stmt.take().expect("future polled after completion");
+        if self.async_enabled {
+            stmt.raii.set_async_enable(false).into_result(&stmt)?;
+        }
+        if has_result {
+            Ok(ResultSetState::Data(Statement::with_raii(stmt.raii)))
+        } else {
+            Ok(ResultSetState::NoData(Statement::with_raii(stmt.raii)))
+        }
+    }
+}
+
+// Nothing is ever pinned in place
+impl<'a, 'b, S, AC: AutocommitMode> Unpin for ExecuteAsync<'a, 'b, S, AC> {}
+
+impl<'a, 'b, S, AC: AutocommitMode> Future for ExecuteAsync<'a, 'b, S, AC> {
+    type Output = Result<ResultSetState<'a, 'b, S, AC>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if this.phase == Phase::Start {
+            let stmt = this.stmt.as_mut().expect("future polled after completion");
+            this.async_enabled = stmt.raii.try_enable_async();
+            this.phase = Phase::Executing;
+        }
+        if this.phase == Phase::Executing {
+            let ret = match this.poll_operation() {
+                Poll::Ready(ret) => ret,
+                Poll::Pending => {
+                    this.pending = true;
+                    this.backoff.wake_later(cx.waker());
+                    return Poll::Pending;
+                }
+            };
+            this.pending = false;
+            this.phase = Phase::Describing;
+            if !ret.into_result(this.stmt.as_ref().unwrap())? {
+                return Poll::Ready(this.finish(false));
+            }
+        }
+        let num_cols = {
+            let stmt = this.stmt.as_mut().expect("future polled after completion");
+            match stmt.raii.poll_num_result_cols() {
+                Poll::Ready(ret) => {
+                    this.pending = false;
+                    ret.into_result(&*stmt)?
+                }
+                Poll::Pending => {
+                    this.pending = true;
+                    this.backoff.wake_later(cx.waker());
+                    return Poll::Pending;
+                }
+            }
+        };
+        Poll::Ready(this.finish(num_cols > 0))
+    }
+}
+
+impl<'a, 'b, S, AC: AutocommitMode> Drop for ExecuteAsync<'a, 'b, S, AC> {
+    fn drop(&mut self) {
+        if !self.pending {
+            return;
+        }
+        // The handle can only be freed once the driver is done with the asynchronous call
+        unsafe {
+            let _ = ffi::SQLCancel(self.stmt.as_ref().unwrap().raii.handle());
+        }
+        // The statement may borrow its connection and parameters, so it can not be handed to
+        // another thread to wait for the driver. Block, but do not spin.
+        let mut backoff = Backoff::new();
+        match self.phase {
+            Phase::Executing => while self.poll_operation().is_pending() {
+                backoff.sleep();
+            },
+            _ => while self.stmt.as_ref().unwrap().raii.poll_num_result_cols().is_pending() {
+                backoff.sleep();
+            },
+        }
+    }
+}
+
+impl<'s, 'a, 'b, S, AC: AutocommitMode> Unpin for FetchAsync<'s, 'a, 'b, S, AC> {}
+
+impl<'s, 'a, 'b, S, AC: AutocommitMode> Future for FetchAsync<'s, 'a, 'b, S, AC> {
+    type Output = Result<Option<Cursor<'s, 'a, 'b, S, AC>>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let ret = {
+            let stmt = this.stmt.as_mut().expect("future polled after completion");
+            if !this.started {
+                this.async_enabled = stmt.raii.try_enable_async();
+                this.started = true;
+            }
+            match stmt.raii.poll_fetch() {
+                Poll::Ready(ret) => ret,
+                Poll::Pending => {
+                    this.pending = true;
+                    this.backoff.wake_later(cx.waker());
+                    return Poll::Pending;
+                }
+            }
+        };
+        this.pending = false;
+        let guard = AsyncModeGuard {
+            stmt: this.stmt.take(),
+            enabled: this.async_enabled,
+        };
+        // Diagnostics need to be read before asynchronous mode is disabled, which clears them
+        let has_row = ret.into_result(guard.statement())?;
+        let stmt = guard.disable()?;
+        if has_row {
+            Poll::Ready(Ok(Some(Cursor {
+                stmt,
+                buffer: vec![0; 512],
+            })))
+        } else {
+            Poll::Ready(Ok(None))
+        }
+    }
+}
+
+/// Disables asynchronous mode once dropped, so the statement stays usable with the blocking API
+/// even if the operation failed.
+struct AsyncModeGuard<'s, 'a: 's, 'b: 's, S: 's, AC: AutocommitMode + 's> {
+    stmt: Option<&'s mut Statement<'a, 'b, S, HasResult, AC>>,
+    enabled: bool,
+}
+
+impl<'s, 'a, 'b, S, AC: AutocommitMode> AsyncModeGuard<'s, 'a, 'b, S, AC> {
+    fn statement(&self) -> &Statement<'a, 'b, S, HasResult, AC> {
+        self.stmt.as_ref().unwrap()
+    }
+
+    /// Disables asynchronous mode, reporting failure to do so
+    fn disable(mut self) -> Result<&'s mut Statement<'a, 'b, S, HasResult, AC>> {
+        let stmt = self.stmt.take().unwrap();
+        if self.enabled {
+            stmt.raii.set_async_enable(false).into_result(&*stmt)?;
+        }
+        Ok(stmt)
+    }
+}
+
+impl<'s, 'a, 'b, S, AC: AutocommitMode> Drop for AsyncModeGuard<'s, 'a, 'b, S, AC> {
+    fn drop(&mut self) {
+        if let Some(ref mut stmt) = self.stmt {
+            if self.enabled {
+                let _ = stmt.raii.set_async_enable(false);
+            }
+        }
+    }
+}
+
+impl<'s, 'a, 'b, S, AC: AutocommitMode> Drop for FetchAsync<'s, 'a, 'b, S, AC> {
+    fn drop(&mut self) {
+        if !self.pending {
+            return;
+        }
+        if let Some(ref mut stmt) = self.stmt {
+            unsafe {
+                let _ = ffi::SQLCancel(stmt.raii.handle());
+            }
+            // See `Drop for ExecuteAsync`
+            let mut backoff = Backoff::new();
+            while stmt.raii.poll_fetch().is_pending() {
+                backoff.sleep();
+            }
+            if self.async_enabled {
+                // Leave the statement usable with the blocking API
+                let _ = stmt.raii.set_async_enable(false);
+            }
+        }
+    }
+}
+
+impl<'p> Raii<'p, ffi::Stmt> {
+    fn set_async_enable(&mut self, enable: bool) -> Return<()> {
+        let value = if enable {
+            SQL_ASYNC_ENABLE_ON
+        } else {
+            SQL_ASYNC_ENABLE_OFF
+        };
+        match unsafe {
+            ffi::SQLSetStmtAttr(
+                self.handle(),
+                ffi::SqlStatementAttribute::SQL_ATTR_ASYNC_ENABLE,
+                value as ffi::SQLPOINTER,
+                0,
+            )
+        } {
+            ffi::SQL_SUCCESS => Return::Success(()),
+            ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(()),
+            ffi::SQL_ERROR => Return::Error("SQLSetStmtAttr"),
+            r => panic!("SQLSetStmtAttr returned unexpected result: {:?}", r),
+        }
+    }
+
+    /// Enables asynchronous mode, returns `false` if the driver does not support it
+    fn try_enable_async(&mut self) -> bool {
+        match self.set_async_enable(true) {
+            Return::Success(()) => true,
+            // Most likely `01S02`, the driver substituted the blocking mode
+            Return::SuccessWithInfo(()) | Return::Error(_) => {
+                debug!("Driver does not support asynchronous execution, falling back to blocking");
+                false
+            }
+        }
+    }
+
+    fn poll_exec_direct(&mut self, text: &SqlText) -> Poll<Return<bool>> {
+        let length = text.len();
+        if length > ffi::SQLINTEGER::MAX as usize {
+            panic!("Statement text too long");
+        }
+        let ret = unsafe {
+            match *text {
+                SqlText::Narrow(ref bytes) => {
+                    ffi::SQLExecDirect(self.handle(), bytes.as_ptr(), length as ffi::SQLINTEGER)
+                }
+                SqlText::Wide(ref units) => {
+                    ffi::SQLExecDirectW(self.handle(), units.as_ptr(), length as ffi::SQLINTEGER)
+                }
+            }
+        };
+        poll_execution("SQLExecDirect", ret)
+    }
+
+    fn poll_execute(&mut self) -> Poll<Return<bool>> {
+        poll_execution("SQLExecute", unsafe { ffi::SQLExecute(self.handle()) })
+    }
+
+    fn poll_fetch(&mut self) -> Poll<Return<bool>> {
+        poll_execution("SQLFetch", unsafe { ffi::SQLFetch(self.handle()) })
+    }
+
+    fn poll_num_result_cols(&self) -> Poll<Return<i16>> {
+        let mut num_cols: ffi::SQLSMALLINT = 0;
+        match unsafe { ffi::SQLNumResultCols(self.handle(), &mut num_cols as *mut ffi::SQLSMALLINT) } {
+            ffi::SQL_STILL_EXECUTING => Poll::Pending,
+            SQL_SUCCESS => Poll::Ready(Return::Success(num_cols)),
+            SQL_SUCCESS_WITH_INFO => Poll::Ready(Return::SuccessWithInfo(num_cols)),
+            SQL_ERROR => Poll::Ready(Return::Error("SQLNumResultCols")),
+            r => panic!("SQLNumResultCols returned unexpected result: {:?}", r),
+        }
+    }
+}
+
+/// Maps the return value of `SQLExecDirect`, `SQLExecute` or `SQLFetch` called in asynchronous
+/// mode. Like their blocking counterparts, `false` indicates `SQL_NO_DATA`.
+fn poll_execution(function: &'static str, ret: ffi::SQLRETURN) -> Poll<Return<bool>> {
+    match ret {
+        ffi::SQL_STILL_EXECUTING => Poll::Pending,
+        ffi::SQL_SUCCESS => Poll::Ready(Return::Success(true)),
+        ffi::SQL_SUCCESS_WITH_INFO => Poll::Ready(Return::SuccessWithInfo(true)),
+        ffi::SQL_ERROR => Poll::Ready(Return::Error(function)),
+        ffi::SQL_NO_DATA => Poll::Ready(Return::Success(false)),
+        ffi::SQL_NEED_DATA => panic!("{} returned SQL_NEED_DATA", function),
+        r => panic!("{} returned unexpected result: {:?}", function, r),
+    }
+}
@@ -1,5 +1,5 @@
-use {ffi, ColumnDescriptor, Raii, Return, Handle, Statement, Result, Prepared, Allocated,
-     NoResult, ResultSetState};
+use {ffi, ColumnDescriptor, HasEncodings, ParameterDescriptor, Raii, Return, Handle, Statement, Result, Prepared, Allocated,
+     NoResult, ResultSetState, SqlText};
 use odbc_safe::AutocommitMode;
 
 impl<'a, 'b, AC: AutocommitMode> Statement<'a, 'b, Allocated, NoResult, AC> {
@@ -83,6 +83,21 @@ impl<'a, 'b, AC: AutocommitMode> Statement<'a, 'b, Prepared, NoResult, AC> {
         self.raii.describe_col(idx).into_result(self)
     }
 
+    /// The number of parameter markers in the statement
+    pub fn num_params(&self) -> Result<i16> {
+        self.raii.num_params().into_result(self)
+    }
+
+    /// Returns description struct for the parameter marker with a given index. Note: indexing is
+    /// starting from 1.
+    ///
+    /// Not all drivers support describing parameters. Those which do not fail with state `HYC00`
+    /// or `IM001`.
+    pub fn describe_param(&self, idx: u16) -> Result<ParameterDescriptor> {
+        let description = self.raii.describe_param(idx).into_result(self)?;
+        Ok(description.to_descriptor())
+    }
+
     /// Executes a prepared statement.
     pub fn execute(mut self) -> Result<ResultSetState<'a, 'b, Prepared, AC>> {
         if self.raii.execute().into_result(&mut self)? {
@@ -100,17 +115,21 @@ impl<'a, 'b, AC: AutocommitMode> Statement<'a, 'b, Prepared, NoResult, AC> {
 
 impl<'p> Raii<'p, ffi::Stmt> {
     fn prepare(&mut self, sql_text: &str) -> Return<()> {
-        let bytes = unsafe { crate::environment::DB_ENCODING }.encode(sql_text).0;
+        let encodings = self.encodings();
+        let text = SqlText::new(sql_text, encodings.db, encodings.wide);
         match unsafe {
-            ffi::SQLPrepare(
-                self.handle(),
-                bytes.as_ptr(),
-                bytes.len() as ffi::SQLINTEGER,
-            )
+            match text {
+                SqlText::Narrow(ref bytes) => {
+                    ffi::SQLPrepare(self.handle(), bytes.as_ptr(), text.len() as ffi::SQLINTEGER)
+                }
+                SqlText::Wide(ref units) => {
+                    ffi::SQLPrepareW(self.handle(), units.as_ptr(), text.len() as ffi::SQLINTEGER)
+                }
+            }
         } {
             ffi::SQL_SUCCESS => Return::Success(()),
             ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(()),
-            ffi::SQL_ERROR => Return::Error,
+            ffi::SQL_ERROR => Return::Error("SQLPrepare"),
             r => panic!("SQLPrepare returned unexpected result: {:?}", r),
         }
     }
@@ -125,7 +144,7 @@ impl<'p> Raii<'p, ffi::Stmt> {
         } {
             ffi::SQL_SUCCESS => Return::Success(()),
             ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(()),
-            ffi::SQL_ERROR => Return::Error,
+            ffi::SQL_ERROR => Return::Error("SQLPrepare"),
             r => panic!("SQLPrepare returned unexpected result: {:?}", r),
         }
     }
@@ -134,7 +153,7 @@ impl<'p> Raii<'p, ffi::Stmt> {
         match unsafe { ffi::SQLExecute(self.handle()) } {
             ffi::SQL_SUCCESS => Return::Success(true),
             ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(true),
-            ffi::SQL_ERROR => Return::Error,
+            ffi::SQL_ERROR => Return::Error("SQLExecute"),
             ffi::SQL_NO_DATA => Return::Success(false),
             r => panic!("SQLExecute returned unexpected result: {:?}", r),
         }
@@ -0,0 +1,404 @@
+//! Splitting SQL scripts into statements and executing them one by one
+use odbc_safe::AutocommitMode;
+use std::error;
+use std::fmt;
+use {ffi, Connection, Error, Handle, Raii, Result, Return};
+
+/// A single statement of a script, as returned by `split_script`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScriptStatement<'a> {
+    /// Line the statement starts on, beginning with `1`
+    pub line: usize,
+    /// Text of the statement, without the terminating `;`
+    pub sql: &'a str,
+}
+
+/// Outcome of a statement executed by `Connection::execute_script`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScriptOutcome {
+    /// Line the statement starts on, beginning with `1`
+    pub line: usize,
+    /// Number of rows affected by the statement, or fetched from the result sets it created.
+    /// `-1` if the driver can not tell.
+    pub row_count: ffi::SQLLEN,
+}
+
+/// Error executing a statement of a script
+#[derive(Debug)]
+pub struct ScriptError {
+    /// Line the failing statement starts on, beginning with `1`
+    pub line: usize,
+    /// Text of the failing statement
+    pub sql: String,
+    /// Diagnostics reported by the driver
+    pub error: Error,
+    /// Outcomes of the statements executed before the failing one
+    pub executed: Vec<ScriptOutcome>,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Statement at line {} failed: {}", self.line, self.error)
+    }
+}
+
+impl error::Error for ScriptError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Separator {
+    /// `;` terminating a statement
+    Semicolon,
+    /// `GO` on a line of its own, terminating a batch
+    Go,
+}
+
+/// Splits `script` into its statements
+///
+/// Statements are terminated by `;`. Terminators within string literals, quoted identifiers
+/// (`"..."`, `[...]` and `` `...` ``), comments and dollar quoted bodies (`$$ ... $$` or
+/// `$tag$ ... $tag$`) are ignored. Backslashes escape quotes only within escape string constants
+/// (`E'...'`), as in standard SQL. Literals of dialects treating backslashes as escapes in every
+/// string, like MySQL by default, must use `''` to embed a quote. If the script contains `GO` on a line of its own, it is split
+/// into the batches separated by `GO` instead, which may contain several statements each.
+/// Statements consisting of nothing but whitespace and comments are skipped.
+///
+/// # Example
+/// ```
+/// # use odbc::*;
+/// let statements = split_script("CREATE TABLE T (A TEXT);\nINSERT INTO T VALUES ('a;b');\n");
+/// assert_eq!(statements.len(), 2);
+/// assert_eq!(statements[1].line, 2);
+/// assert_eq!(statements[1].sql, "INSERT INTO T VALUES ('a;b')");
+/// ```
+pub fn split_script<'a>(script: &'a str) -> Vec<ScriptStatement<'a>> {
+    let separators = separators(script);
+    let go = separators.iter().any(|&(_, _, kind)| kind == Separator::Go);
+    let mut statements = Vec::new();
+    let mut lines = LineCounter { position: 0, line: 1 };
+    let mut start = 0;
+    for &(begin, end, kind) in &separators {
+        if go && kind == Separator::Semicolon {
+            continue;
+        }
+        push_statement(script, start, begin, &mut lines, &mut statements);
+        start = end;
+    }
+    push_statement(script, start, script.len(), &mut lines, &mut statements);
+    statements
+}
+
+/// Line of the last position asked for, so each line break is only counted once
+struct LineCounter {
+    position: usize,
+    line: usize,
+}
+
+impl LineCounter {
+    /// Line of `position`, which must not precede any position asked for before
+    fn line_at(&mut self, bytes: &[u8], position: usize) -> usize {
+        self.line += bytes[self.position..position].iter().filter(|&&b| b == b'\n').count();
+        self.position = position;
+        self.line
+    }
+}
+
+/// Adds `script[start..end]` to `statements`, unless it has no content besides comments
+fn push_statement<'a>(
+    script: &'a str,
+    start: usize,
+    end: usize,
+    lines: &mut LineCounter,
+    statements: &mut Vec<ScriptStatement<'a>>,
+) {
+    let bytes = script.as_bytes();
+    let mut i = start;
+    while i < end {
+        if bytes[i].is_ascii_whitespace() {
+            i += 1;
+        } else if bytes[i..end].starts_with(b"--") {
+            i = find(bytes, i, end, b"\n").map_or(end, |p| p + 1);
+        } else if bytes[i..end].starts_with(b"/*") {
+            i = find(bytes, i + 2, end, b"*/").map_or(end, |p| p + 2);
+        } else {
+            statements.push(ScriptStatement {
+                line: lines.line_at(bytes, i),
+                sql: script[i..end].trim_end(),
+            });
+            return;
+        }
+    }
+}
+
+/// Position of the first occurrence of `pattern` in `bytes[from..end]`
+fn find(bytes: &[u8], from: usize, end: usize, pattern: &[u8]) -> Option<usize> {
+    if from > end {
+        return None;
+    }
+    bytes[from..end].windows(pattern.len()).position(|w| w == pattern).map(|p| p + from)
+}
+
+/// Position after the end of the escape string constant (`E'...'`) whose content starts at `from`.
+/// Within these, a backslash escapes the following character.
+fn escape_string_end(bytes: &[u8], from: usize) -> usize {
+    let mut i = from;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            // A doubled quote within the constant stands for a single one
+            b'\'' if bytes.get(i + 1) == Some(&b'\'') => i += 2,
+            b'\'' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn is_identifier_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Byte ranges and kinds of all separators in `script`. All delimiters are ASCII, so scanning the
+/// bytes never splits a multi byte character.
+// `Option::is_some_and` would require Rust 1.70
+#[allow(unknown_lints, clippy::unnecessary_map_or)]
+fn separators(script: &str) -> Vec<(usize, usize, Separator)> {
+    let bytes = script.as_bytes();
+    let len = bytes.len();
+    let mut separators = Vec::new();
+    let mut i = 0;
+    while i < len {
+        if i == 0 || bytes[i - 1] == b'\n' {
+            if let Some(end) = go_line(bytes, i) {
+                separators.push((i, end, Separator::Go));
+                i = end;
+                continue;
+            }
+        }
+        i = match bytes[i] {
+            b'E' | b'e' if bytes.get(i + 1) == Some(&b'\'') && (i == 0 || !is_identifier_byte(bytes[i - 1])) => {
+                escape_string_end(bytes, i + 2)
+            }
+            delimiter @ b'\'' | delimiter @ b'"' | delimiter @ b'`' | delimiter @ b'[' => {
+                let end = if delimiter == b'[' { b']' } else { delimiter };
+                // A doubled delimiter ends the literal and starts a new one, so it needs no special
+                // care
+                find(bytes, i + 1, len, &[end]).map_or(len, |p| p + 1)
+            }
+            b'-' if bytes[i..].starts_with(b"--") => find(bytes, i, len, b"\n").unwrap_or(len),
+            b'/' if bytes[i..].starts_with(b"/*") => find(bytes, i + 2, len, b"*/").map_or(len, |p| p + 2),
+            b'$' if i == 0 || !is_identifier_byte(bytes[i - 1]) => {
+                let mut tag_end = i + 1;
+                while tag_end < len && is_identifier_byte(bytes[tag_end]) {
+                    tag_end += 1;
+                }
+                let is_tag = tag_end < len
+                    && bytes[tag_end] == b'$'
+                    && !bytes.get(i + 1).map_or(false, |b| b.is_ascii_digit());
+                if is_tag {
+                    let tag = &bytes[i..=tag_end];
+                    find(bytes, tag_end + 1, len, tag).map_or(len, |p| p + tag.len())
+                } else {
+                    i + 1
+                }
+            }
+            b';' => {
+                separators.push((i, i + 1, Separator::Semicolon));
+                i + 1
+            }
+            _ => i + 1,
+        };
+    }
+    separators
+}
+
+/// If the line starting at `start` consists of `GO` and whitespace only, returns the position after
+/// its line break.
+fn go_line(bytes: &[u8], start: usize) -> Option<usize> {
+    let end = find(bytes, start, bytes.len(), b"\n").map_or(bytes.len(), |p| p + 1);
+    let line = &bytes[start..end];
+    let first = line.iter().position(|b| !b.is_ascii_whitespace())?;
+    let last = line.iter().rposition(|b| !b.is_ascii_whitespace())?;
+    if line[first..=last].eq_ignore_ascii_case(b"GO") {
+        Some(end)
+    } else {
+        None
+    }
+}
+
+impl<'env, AC: AutocommitMode> Connection<'env, AC> {
+    /// Executes the statements of `script` one after another, as split by `split_script`
+    ///
+    /// All statements are executed on the same statement handle. Result sets are fetched to the
+    /// end and discarded. Execution stops at the first failing statement.
+    ///
+    /// # Example
+    /// ```
+    /// # use odbc::*;
+    /// # fn doc() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    /// let env = create_environment_v3().map_err(|e| e.unwrap())?;
+    /// let conn = env.connect("TestDataSource", "", "")?;
+    /// let outcomes = conn.execute_script("SELECT TITLE FROM MOVIES;\nSELECT YEAR FROM MOVIES;")?;
+    /// for outcome in outcomes {
+    ///     println!("line {}: {} rows", outcome.line, outcome.row_count);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_script(&self, script: &str) -> ::std::result::Result<Vec<ScriptOutcome>, ScriptError> {
+        let statements = split_script(script);
+        let mut executed = Vec::with_capacity(statements.len());
+        let mut raii = match statements.first() {
+            None => return Ok(executed),
+            Some(first) => Raii::with_parent(self).into_result(self).map_err(|error| ScriptError {
+                line: first.line,
+                sql: first.sql.to_owned(),
+                error,
+                executed: Vec::new(),
+            })?,
+        };
+        for statement in statements {
+            match raii.execute_script_statement(statement.sql) {
+                Ok(row_count) => executed.push(ScriptOutcome {
+                    line: statement.line,
+                    row_count,
+                }),
+                Err(error) => {
+                    return Err(ScriptError {
+                        line: statement.line,
+                        sql: statement.sql.to_owned(),
+                        error,
+                        executed,
+                    })
+                }
+            }
+        }
+        Ok(executed)
+    }
+}
+
+impl<'p> Raii<'p, ffi::Stmt> {
+    /// Executes `sql` and consumes all of its result sets. Returns the number of affected or
+    /// fetched rows.
+    fn execute_script_statement(&mut self, sql: &str) -> Result<ffi::SQLLEN> {
+        if !self.exec_direct(sql).into_result(self)? {
+            // `SQL_NO_DATA`, e.g. a searched `UPDATE` which did not match any rows
+            return Ok(0);
+        }
+        let mut row_count: ffi::SQLLEN = 0;
+        loop {
+            if self.num_result_cols().into_result(self)? > 0 {
+                while self.fetch().into_result(self)? {
+                    // Stays `-1` once the driver could not tell the count of an earlier result
+                    if row_count >= 0 {
+                        row_count += 1;
+                    }
+                }
+            } else {
+                let affected = self.affected_row_count().into_result(self)?;
+                if affected < 0 {
+                    row_count = -1;
+                } else if row_count >= 0 {
+                    row_count += affected;
+                }
+            }
+            if !self.more_results().into_result(self)? {
+                return Ok(row_count);
+            }
+        }
+    }
+
+    fn more_results(&mut self) -> Return<bool> {
+        match unsafe { ffi::SQLMoreResults(self.handle()) } {
+            ffi::SQL_SUCCESS => Return::Success(true),
+            ffi::SQL_SUCCESS_WITH_INFO => Return::SuccessWithInfo(true),
+            ffi::SQL_NO_DATA => Return::Success(false),
+            ffi::SQL_ERROR => Return::Error("SQLMoreResults"),
+            r => panic!("SQLMoreResults returned unexpected result: {:?}", r),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn split(script: &str) -> Vec<(usize, &str)> {
+        split_script(script).into_iter().map(|s| (s.line, s.sql)).collect()
+    }
+
+    #[test]
+    fn semicolons() {
+        let script = "CREATE TABLE T (A TEXT);\n\n  INSERT INTO T VALUES ('a');\nSELECT * FROM T";
+        assert_eq!(
+            split(script),
+            vec![
+                (1, "CREATE TABLE T (A TEXT)"),
+                (3, "INSERT INTO T VALUES ('a')"),
+                (4, "SELECT * FROM T"),
+            ]
+        );
+    }
+
+    #[test]
+    fn quoting_and_comments() {
+        let script = "SELECT 'a;''b', \"c;\", [d;], `e;` -- f;\n/* g; */ FROM T;\n-- trailing;\n";
+        assert_eq!(
+            split(script),
+            vec![(1, "SELECT 'a;''b', \"c;\", [d;], `e;` -- f;\n/* g; */ FROM T")]
+        );
+        let script = "SELECT E'it\\'s; x', e'a''b\\\\'; SELECT 'C:\\'; SELECT TYPE'a;';";
+        assert_eq!(
+            split(script),
+            vec![
+                (1, "SELECT E'it\\'s; x', e'a''b\\\\'"),
+                (1, "SELECT 'C:\\'"),
+                (1, "SELECT TYPE'a;'"),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_before_statement() {
+        let script = "-- Migration 1\n/* creates\n T */\nCREATE TABLE T (A TEXT);";
+        assert_eq!(split(script), vec![(4, "CREATE TABLE T (A TEXT)")]);
+    }
+
+    #[test]
+    fn dollar_quoting() {
+        let script = "CREATE FUNCTION f() RETURNS int AS $$ BEGIN RETURN 1; END; $$ LANGUAGE plpgsql;\n\
+                      CREATE FUNCTION g() RETURNS int AS $body$ SELECT $1; $body$ LANGUAGE sql;\n\
+                      SELECT a$b FROM T;";
+        assert_eq!(
+            split(script),
+            vec![
+                (1, "CREATE FUNCTION f() RETURNS int AS $$ BEGIN RETURN 1; END; $$ LANGUAGE plpgsql"),
+                (2, "CREATE FUNCTION g() RETURNS int AS $body$ SELECT $1; $body$ LANGUAGE sql"),
+                (3, "SELECT a$b FROM T"),
+            ]
+        );
+    }
+
+    #[test]
+    fn go_batches() {
+        let script = "CREATE PROCEDURE p AS\nBEGIN\n  SELECT 1;\n  SELECT 2;\nEND\nGO\n\
+                      SELECT 'GO'; -- GO\n  go  \nGOTO:\nSELECT 3\n";
+        assert_eq!(
+            split(script),
+            vec![
+                (1, "CREATE PROCEDURE p AS\nBEGIN\n  SELECT 1;\n  SELECT 2;\nEND"),
+                (7, "SELECT 'GO'; -- GO"),
+                (9, "GOTO:\nSELECT 3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_script() {
+        assert_eq!(split(""), vec![]);
+        assert_eq!(split(" ;\n; -- nothing"), vec![]);
+    }
+}
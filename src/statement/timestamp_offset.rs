@@ -0,0 +1,321 @@
+//! Timestamps with time zone offset, e.g. `DATETIMEOFFSET` or `TIMESTAMP WITH TIME ZONE`
+//!
+//! SQL Server exchanges `DATETIMEOFFSET` as `SQL_SS_TIMESTAMPOFFSET_STRUCT`. Other drivers have no
+//! C type for timestamps with time zone, so `Cursor::get_timestamp_offset` fetches those as text
+//! and parses it.
+use super::types::{fraction_digits, EncodedValue, OdbcType};
+use super::Cursor;
+use odbc_safe::AutocommitMode;
+use std::fmt;
+use std::mem::size_of;
+use std::ptr::read_unaligned;
+use std::slice::from_raw_parts;
+use std::str::FromStr;
+use {ffi, DiagnosticRecord, Result};
+
+/// Largest number of fractional second digits of a SQL Server `DATETIMEOFFSET`
+const MAX_OFFSET_FRACTION_DIGITS: ffi::SQLSMALLINT = 7;
+
+/// Timestamp with time zone offset, with the same layout as `SQL_SS_TIMESTAMPOFFSET_STRUCT`
+///
+/// Date and time are local to the offset, i.e. `2021-03-14 15:09:26 +01:00` is stored with
+/// `hour: 15` and `timezone_hour: 1`. For negative offsets both `timezone_hour` and
+/// `timezone_minute` are negative.
+///
+/// # Example
+/// ```
+/// # use odbc::*;
+/// let ts: SqlTimestampOffset = "2021-03-14 15:09:26.5-05:30".parse().unwrap();
+/// assert_eq!((ts.hour, ts.fraction), (15, 500_000_000));
+/// assert_eq!(ts.offset_seconds(), -19_800);
+/// ```
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SqlTimestampOffset {
+    pub year: ffi::SQLSMALLINT,
+    pub month: ffi::SQLUSMALLINT,
+    pub day: ffi::SQLUSMALLINT,
+    pub hour: ffi::SQLUSMALLINT,
+    pub minute: ffi::SQLUSMALLINT,
+    pub second: ffi::SQLUSMALLINT,
+    /// Nanoseconds
+    pub fraction: ffi::SQLUINTEGER,
+    pub timezone_hour: ffi::SQLSMALLINT,
+    pub timezone_minute: ffi::SQLSMALLINT,
+}
+
+impl SqlTimestampOffset {
+    /// Offset from UTC in seconds
+    pub fn offset_seconds(&self) -> i32 {
+        self.timezone_hour as i32 * 3_600 + self.timezone_minute as i32 * 60
+    }
+}
+
+impl fmt::Display for SqlTimestampOffset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        if self.fraction != 0 {
+            let fraction = format!("{:09}", self.fraction);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        let offset = self.offset_seconds();
+        let sign = if offset < 0 { '-' } else { '+' };
+        write!(f, " {}{:02}:{:02}", sign, offset.abs() / 3_600, offset.abs() % 3_600 / 60)
+    }
+}
+
+/// Parses the text representation drivers use for timestamps with time zone, i.e.
+/// `yyyy-mm-dd hh:mm:ss[.f...][ ](Z|+hh[[:]mm]|-hh[[:]mm])`. `T` is accepted as separator between
+/// date and time. Digits beyond nanoseconds are truncated.
+impl FromStr for SqlTimestampOffset {
+    type Err = TimestampOffsetError;
+
+    fn from_str(s: &str) -> ::std::result::Result<SqlTimestampOffset, TimestampOffsetError> {
+        let mut parser = Parser(s.trim().as_bytes());
+        let year = parser.number(4)?;
+        parser.expect(b"-")?;
+        let month = parser.number(2)?;
+        parser.expect(b"-")?;
+        let day = parser.number(2)?;
+        parser.expect(b" T")?;
+        let hour = parser.number(2)?;
+        parser.expect(b":")?;
+        let minute = parser.number(2)?;
+        parser.expect(b":")?;
+        let second = parser.number(2)?;
+        let mut fraction = 0;
+        if parser.accept(b".") {
+            let digits = parser.digits();
+            if digits.is_empty() {
+                return Err(TimestampOffsetError);
+            }
+            for i in 0..9 {
+                fraction = fraction * 10 + digits.get(i).map_or(0, |d| (d - b'0') as u32);
+            }
+        }
+        parser.accept(b" ");
+        let (timezone_hour, timezone_minute) = if parser.accept(b"Z") {
+            (0, 0)
+        } else {
+            let negative = parser.peek() == Some(b'-');
+            parser.expect(b"+-")?;
+            let hour = parser.number(2)? as i16;
+            let colon = parser.accept(b":");
+            let minute = if colon || !parser.is_empty() { parser.number(2)? as i16 } else { 0 };
+            if negative { (-hour, -minute) } else { (hour, minute) }
+        };
+        if !parser.is_empty() || !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23
+            || minute > 59 || second > 60 || timezone_hour.abs() > 14 || timezone_minute.abs() > 59
+        {
+            return Err(TimestampOffsetError);
+        }
+        Ok(SqlTimestampOffset {
+            year: year as i16,
+            month: month as u16,
+            day: day as u16,
+            hour: hour as u16,
+            minute: minute as u16,
+            second: second as u16,
+            fraction,
+            timezone_hour,
+            timezone_minute,
+        })
+    }
+}
+
+struct Parser<'a>(&'a [u8]);
+
+impl<'a> Parser<'a> {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.0.first().cloned()
+    }
+
+    /// Consumes the next byte if it is one of `any`
+    fn accept(&mut self, any: &[u8]) -> bool {
+        match self.peek() {
+            Some(b) if any.contains(&b) => {
+                self.0 = &self.0[1..];
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, any: &[u8]) -> ::std::result::Result<(), TimestampOffsetError> {
+        if self.accept(any) { Ok(()) } else { Err(TimestampOffsetError) }
+    }
+
+    fn digits(&mut self) -> &'a [u8] {
+        let len = self.0.iter().take_while(|b| b.is_ascii_digit()).count();
+        let (digits, rest) = self.0.split_at(len);
+        self.0 = rest;
+        digits
+    }
+
+    /// Parses exactly `len` digits
+    fn number(&mut self, len: usize) -> ::std::result::Result<u32, TimestampOffsetError> {
+        if self.0.len() < len || !self.0[..len].iter().all(|b| b.is_ascii_digit()) {
+            return Err(TimestampOffsetError);
+        }
+        let (digits, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(digits.iter().fold(0, |n, d| n * 10 + (d - b'0') as u32))
+    }
+}
+
+/// Exchanged as `SQL_C_BINARY`, which SQL Server maps to `SQL_SS_TIMESTAMPOFFSET_STRUCT`. Bound as
+/// encoded value, since the length of the buffer differs from the column size.
+unsafe impl<'a> OdbcType<'a> for SqlTimestampOffset {
+    fn sql_data_type() -> ffi::SqlDataType {
+        ffi::SQL_SS_TIMESTAMPOFFSET
+    }
+    fn c_data_type() -> ffi::SqlCDataType {
+        // NOTE: ODBC 3.5 and earlier
+        ffi::SQL_C_BINARY
+    }
+
+    fn convert(buffer: &'a [u8]) -> Self {
+        assert_eq!(buffer.len(), size_of::<Self>());
+        unsafe { read_unaligned(buffer.as_ptr() as *const Self) }
+    }
+
+    /// Number of characters of `yyyy-mm-dd hh:mm:ss[.fffffff] +hh:mm`
+    fn column_size(&self) -> ffi::SQLULEN {
+        match self.decimal_digits() {
+            0 => 26,
+            digits => 27 + digits as ffi::SQLULEN,
+        }
+    }
+    fn decimal_digits(&self) -> ffi::SQLSMALLINT {
+        fraction_digits(self.fraction).min(MAX_OFFSET_FRACTION_DIGITS)
+    }
+    fn value_ptr(&self) -> ffi::SQLPOINTER {
+        self as *const Self as ffi::SQLPOINTER
+    }
+
+    fn encoded_value(&self) -> EncodedValue {
+        let bytes = unsafe { from_raw_parts(self as *const Self as *const u8, size_of::<Self>()) };
+        EncodedValue::with_column_size(bytes.to_vec(), self.column_size())
+    }
+}
+
+impl<'a, 'b, 'c, S, AC: AutocommitMode> Cursor<'a, 'b, 'c, S, AC> {
+    /// Retrieves a timestamp with time zone offset from any driver
+    ///
+    /// `DATETIMEOFFSET` columns of SQL Server are fetched as `SqlTimestampOffset` directly, all
+    /// other columns are fetched as text and parsed. Fails with state `22007` if the text is not
+    /// a timestamp with offset.
+    pub fn get_timestamp_offset(&mut self, col_or_param_num: u16) -> Result<Option<SqlTimestampOffset>> {
+        let concise_type = self.stmt.raii.concise_type(col_or_param_num).into_result(&self.stmt.raii)?;
+        if concise_type == ffi::SQL_SS_TIMESTAMPOFFSET as ffi::SQLSMALLINT {
+            return self.get_data::<SqlTimestampOffset>(col_or_param_num);
+        }
+        match self.get_data::<String>(col_or_param_num)? {
+            Some(text) => text.parse().map(Some).map_err(|_| {
+                DiagnosticRecord::with_message(
+                    b"22007",
+                    &format!("Invalid timestamp with time zone: {}", text),
+                ).into()
+            }),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_conversions {
+    use super::{SqlTimestampOffset, TimestampOffsetError};
+    use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, TimeZone, Timelike};
+    use std::convert::TryFrom;
+
+    impl TryFrom<SqlTimestampOffset> for DateTime<FixedOffset> {
+        type Error = TimestampOffsetError;
+
+        fn try_from(value: SqlTimestampOffset) -> Result<DateTime<FixedOffset>, TimestampOffsetError> {
+            let offset = FixedOffset::east_opt(value.offset_seconds()).ok_or(TimestampOffsetError)?;
+            let local = NaiveDate::from_ymd_opt(value.year as i32, value.month as u32, value.day as u32)
+                .and_then(|date| {
+                    date.and_hms_nano_opt(
+                        value.hour as u32,
+                        value.minute as u32,
+                        value.second as u32,
+                        value.fraction,
+                    )
+                })
+                .ok_or(TimestampOffsetError)?;
+            offset.from_local_datetime(&local).single().ok_or(TimestampOffsetError)
+        }
+    }
+
+    impl TryFrom<DateTime<FixedOffset>> for SqlTimestampOffset {
+        type Error = TimestampOffsetError;
+
+        /// Fails if the year is out of the range of `i16`
+        fn try_from(value: DateTime<FixedOffset>) -> Result<SqlTimestampOffset, TimestampOffsetError> {
+            let offset = value.offset().local_minus_utc();
+            Ok(SqlTimestampOffset {
+                year: i16::try_from(value.year()).map_err(|_| TimestampOffsetError)?,
+                month: value.month() as u16,
+                day: value.day() as u16,
+                hour: value.hour() as u16,
+                minute: value.minute() as u16,
+                second: value.second() as u16,
+                // Leap seconds are represented by nanoseconds exceeding one second
+                fraction: value.nanosecond().min(999_999_999),
+                timezone_hour: (offset / 3_600) as i16,
+                timezone_minute: (offset % 3_600 / 60) as i16,
+            })
+        }
+    }
+}
+
+/// A timestamp with time zone could not be parsed or converted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimestampOffsetError;
+
+impl fmt::Display for TimestampOffsetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid timestamp with time zone")
+    }
+}
+
+impl ::std::error::Error for TimestampOffsetError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn layout_matches_sql_ss_timestampoffset_struct() {
+        assert_eq!(size_of::<SqlTimestampOffset>(), size_of::<ffi::SQL_SS_TIMESTAMPOFFSET_STRUCT>());
+    }
+
+    #[test]
+    fn parse_driver_formats() {
+        // PostgreSQL
+        let ts: SqlTimestampOffset = "2021-03-14 15:09:26.535897+01".parse().unwrap();
+        assert_eq!((ts.fraction, ts.timezone_hour, ts.timezone_minute), (535_897_000, 1, 0));
+        // Oracle
+        let ts: SqlTimestampOffset = "2021-03-14 15:09:26.000000 -05:30".parse().unwrap();
+        assert_eq!((ts.timezone_hour, ts.timezone_minute), (-5, -30));
+        // ISO 8601
+        let ts: SqlTimestampOffset = "2021-03-14T15:09:26Z".parse().unwrap();
+        assert_eq!(ts.offset_seconds(), 0);
+        assert!("2021-03-14 15:09:26".parse::<SqlTimestampOffset>().is_err());
+        assert!("2021-13-14 15:09:26+01".parse::<SqlTimestampOffset>().is_err());
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let text = "2021-03-14 15:09:26.5 -05:30";
+        assert_eq!(text.parse::<SqlTimestampOffset>().unwrap().to_string(), text);
+    }
+}
@@ -1,4 +1,7 @@
-use ffi;
+use {ffi, DecodeError, DecodeErrorKind, DecodePolicy, Raii};
+use encoding_rs::{Encoding, UTF_8};
+use std::borrow::Cow;
+use std::str::from_utf8;
 use std::slice::from_raw_parts;
 use std::mem::{size_of, transmute};
 use std::ffi::CString;
@@ -6,11 +9,23 @@ use std::borrow::Cow::{Borrowed, Owned};
 
 pub struct EncodedValue {
     pub buf: Option<Vec<u8>>,
+    column_size: Option<ffi::SQLULEN>,
 }
 
 impl EncodedValue {
     pub fn new(buf: Option<Vec<u8>>) -> Self {
-        Self { buf }
+        Self { buf, column_size: None }
+    }
+
+    /// Encoded value whose column size differs from the length of the buffer, e.g. a timestamp
+    /// converted into a `SqlTimestamp`
+    pub fn with_column_size(buf: Vec<u8>, column_size: ffi::SQLULEN) -> Self {
+        Self { buf: Some(buf), column_size: Some(column_size) }
+    }
+
+    /// Length of the buffer in bytes
+    pub fn buffer_length(&self) -> ffi::SQLLEN {
+        self.buf.as_ref().map_or(0, |buf| buf.len() as ffi::SQLLEN)
     }
 
     pub fn has_value(&self) -> bool {
@@ -18,7 +33,9 @@ impl EncodedValue {
     }
 
     pub fn column_size(&self) -> ffi::SQLULEN {
-        if let Some(buf) = &self.buf {
+        if let Some(column_size) = self.column_size {
+            column_size
+        } else if let Some(buf) = &self.buf {
             buf.len() as ffi::SQLULEN
         } else {
             0
@@ -34,9 +51,23 @@ impl EncodedValue {
     }
 }
 
+/// Types which can be bound as parameters or retrieved from a result set
+///
+/// # Safety
+///
+/// `value_ptr` and `column_size` must describe a valid buffer of `c_data_type()`. The buffer is
+/// read by the driver once the statement is executed, which may happen on another thread, since
+/// `Statement` is `Send`. Implementations must therefore not point into memory which can be
+/// mutated through a shared reference.
 pub unsafe trait OdbcType<'a>: Sized {
     fn sql_data_type() -> ffi::SqlDataType;
     fn c_data_type() -> ffi::SqlCDataType;
+    /// Converts the data of a column. Can not report data which is not valid, so character data is
+    /// assumed to be UTF-8 and decoded lossily, and `&str` stops at the first invalid byte.
+    #[deprecated(
+        since = "0.18.0",
+        note = "use `convert_with_encoding`, which honours the decode policy and reports invalid data"
+    )]
     fn convert(_: &'a [u8]) -> Self;
     fn column_size(&self) -> ffi::SQLULEN;
     fn null_bytes_count() -> usize {
@@ -47,6 +78,42 @@ pub unsafe trait OdbcType<'a>: Sized {
         0
     }
     fn encoded_value(&self) -> EncodedValue;
+    /// Like `convert`, but decodes character data using the encoding and decode policy of the
+    /// connection, and reports data which can not be represented as error. Defaults to `convert`,
+    /// only string types and types with invalid values need to override it.
+    fn convert_with_encoding(
+        buffer: &'a [u8],
+        _encoding: &'static Encoding,
+        _policy: DecodePolicy,
+    ) -> ::Result<Self> {
+        #[allow(deprecated)]
+        Ok(Self::convert(buffer))
+    }
+    /// Like `encoded_value`, but encodes character data using the encoding of the connection, and
+    /// reports values which can not be bound as error. Defaults to `encoded_value`, only string
+    /// types and types which can not bind all of their values need to override it.
+    fn encoded_value_with_encoding(
+        &self,
+        _encoding: &'static Encoding,
+    ) -> ::Result<EncodedValue> {
+        Ok(self.encoded_value())
+    }
+    /// C and SQL type to bind `value` with, `None` standing for `NULL`. Defaults to `c_data_type`
+    /// and `sql_data_type`, only types whose types depend on the value need to override it.
+    fn bind_types(_value: Option<&Self>) -> (ffi::SQLSMALLINT, ffi::SQLSMALLINT) {
+        (Self::c_data_type() as ffi::SQLSMALLINT, Self::sql_data_type() as ffi::SQLSMALLINT)
+    }
+    /// Called after the value has been bound to the parameter at `parameter_index`, to set fields
+    /// of its APD record which `SQLBindParameter` does not cover. Does nothing by default.
+    fn bind_descriptor(&self, _stmt: &mut Raii<ffi::Stmt>, _parameter_index: u16) -> ::Result<()> {
+        Ok(())
+    }
+    /// Target type `SQLGetData` retrieves column `col` as. Types which need to set fields of the
+    /// ARD record of the column first set them here and return `SQL_ARD_TYPE`. Defaults to
+    /// `c_data_type`.
+    fn get_data_target_type(_stmt: &mut Raii<ffi::Stmt>, _col: u16) -> ::Result<ffi::SQLSMALLINT> {
+        Ok(Self::c_data_type() as ffi::SQLSMALLINT)
+    }
 }
 
 unsafe impl<'a> OdbcType<'a> for &'a[u8] {
@@ -196,15 +263,15 @@ unsafe impl<'a> OdbcType<'a> for String {
     }
 
     fn convert(buffer: &'a [u8]) -> Self {
-        unsafe { ::environment::DB_ENCODING }.decode(buffer).0.to_string()
+        String::from_utf8_lossy(buffer).into_owned()
     }
 
     fn column_size(&self) -> ffi::SQLULEN {
-        unsafe { ::environment::DB_ENCODING }.encode(&self).0.len() as ffi::SQLULEN
+        self.len() as ffi::SQLULEN
     }
 
     fn value_ptr(&self) -> ffi::SQLPOINTER {
-        unsafe { ::environment::DB_ENCODING }.encode(&self).0.as_ptr() as *const Self as ffi::SQLPOINTER
+        self.as_ptr() as *const Self as ffi::SQLPOINTER
     }
 
     fn null_bytes_count() -> usize {
@@ -212,7 +279,22 @@ unsafe impl<'a> OdbcType<'a> for String {
     }
     
     fn encoded_value(&self) -> EncodedValue {
-        EncodedValue::new(Some(unsafe { ::environment::DB_ENCODING }.encode(&self).0.to_vec()))
+        EncodedValue::new(Some(self.as_bytes().to_vec()))
+    }
+
+    fn convert_with_encoding(
+        buffer: &'a [u8],
+        encoding: &'static Encoding,
+        policy: DecodePolicy,
+    ) -> ::Result<Self> {
+        Ok(decode(buffer, encoding, policy)?.into_owned())
+    }
+
+    fn encoded_value_with_encoding(
+        &self,
+        encoding: &'static Encoding,
+    ) -> ::Result<EncodedValue> {
+        Ok(EncodedValue::new(Some(encoding.encode(&self).0.to_vec())))
     }
 }
 
@@ -225,19 +307,18 @@ unsafe impl<'a> OdbcType<'a> for &'a str {
     }
 
     fn convert(buffer: &'a [u8]) -> Self {
-        let cow = unsafe { ::environment::DB_ENCODING }.decode(buffer).0;
-        match cow {
-            Borrowed(strref) => strref,
-            Owned(_string) => panic!("Couldn't convert data to `&str`. Try `String` or `Cow<str>` instead."),
+        match from_utf8(buffer) {
+            Ok(s) => s,
+            Err(error) => from_utf8(&buffer[..error.valid_up_to()]).unwrap(),
         }
     }
 
     fn column_size(&self) -> ffi::SQLULEN {
-        unsafe { ::environment::DB_ENCODING }.encode(self).0.len() as ffi::SQLULEN
+        self.len() as ffi::SQLULEN
     }
 
     fn value_ptr(&self) -> ffi::SQLPOINTER {
-        unsafe { ::environment::DB_ENCODING }.encode(self).0.as_ptr() as *const Self as ffi::SQLPOINTER
+        self.as_ptr() as *const Self as ffi::SQLPOINTER
     }
 
     fn null_bytes_count() -> usize {
@@ -245,7 +326,25 @@ unsafe impl<'a> OdbcType<'a> for &'a str {
     }
     
     fn encoded_value(&self) -> EncodedValue {
-        EncodedValue::new(Some(unsafe { ::environment::DB_ENCODING }.encode(&self).0.to_vec()))
+        EncodedValue::new(Some(self.as_bytes().to_vec()))
+    }
+
+    fn convert_with_encoding(
+        buffer: &'a [u8],
+        encoding: &'static Encoding,
+        policy: DecodePolicy,
+    ) -> ::Result<Self> {
+        match decode(buffer, encoding, policy)? {
+            Borrowed(strref) => Ok(strref),
+            Owned(_string) => Err(DecodeError::new(DecodeErrorKind::NotBorrowable, encoding, buffer).into()),
+        }
+    }
+
+    fn encoded_value_with_encoding(
+        &self,
+        encoding: &'static Encoding,
+    ) -> ::Result<EncodedValue> {
+        Ok(EncodedValue::new(Some(encoding.encode(self).0.to_vec())))
     }
 }
 
@@ -258,15 +357,15 @@ unsafe impl<'a> OdbcType<'a> for ::std::borrow::Cow<'a, str> {
     }
 
     fn convert(buffer: &'a [u8]) -> Self {
-        unsafe {::environment::DB_ENCODING.decode(buffer).0}
+        String::from_utf8_lossy(buffer)
     }
 
     fn column_size(&self) -> ffi::SQLULEN {
-        unsafe { ::environment::DB_ENCODING }.encode(self).0.len() as ffi::SQLULEN
+        self.len() as ffi::SQLULEN
     }
 
     fn value_ptr(&self) -> ffi::SQLPOINTER {
-        unsafe { ::environment::DB_ENCODING }.encode(self).0.as_ptr() as *const Self as ffi::SQLPOINTER
+        self.as_ptr() as *const Self as ffi::SQLPOINTER
     }
 
     fn null_bytes_count() -> usize {
@@ -274,7 +373,40 @@ unsafe impl<'a> OdbcType<'a> for ::std::borrow::Cow<'a, str> {
     }
     
     fn encoded_value(&self) -> EncodedValue {
-        EncodedValue::new(Some(unsafe { ::environment::DB_ENCODING }.encode(self).0.to_vec()))
+        EncodedValue::new(Some(self.as_bytes().to_vec()))
+    }
+
+    fn convert_with_encoding(
+        buffer: &'a [u8],
+        encoding: &'static Encoding,
+        policy: DecodePolicy,
+    ) -> ::Result<Self> {
+        Ok(decode(buffer, encoding, policy)?)
+    }
+
+    fn encoded_value_with_encoding(
+        &self,
+        encoding: &'static Encoding,
+    ) -> ::Result<EncodedValue> {
+        Ok(EncodedValue::new(Some(encoding.encode(self).0.to_vec())))
+    }
+}
+
+/// Decodes character data returned by the driver according to `policy`
+fn decode<'a>(
+    buffer: &'a [u8],
+    encoding: &'static Encoding,
+    policy: DecodePolicy,
+) -> Result<Cow<'a, str>, DecodeError> {
+    let malformed = || DecodeError::new(DecodeErrorKind::Malformed, encoding, buffer);
+    match policy {
+        DecodePolicy::Lossy => Ok(encoding.decode(buffer).0),
+        DecodePolicy::Strict => encoding
+            .decode_without_bom_handling_and_without_replacement(buffer)
+            .ok_or_else(malformed),
+        DecodePolicy::Raw => from_utf8(buffer)
+            .map(Borrowed)
+            .map_err(|_| DecodeError::new(DecodeErrorKind::Malformed, UTF_8, buffer)),
     }
 }
 
@@ -628,8 +760,12 @@ unsafe impl<'a> OdbcType<'a> for SqlTimestamp {
         }
     }
 
+    /// Number of characters of `yyyy-mm-dd hh:mm:ss[.f...]`
     fn column_size(&self) -> ffi::SQLULEN {
-        size_of::<Self>() as ffi::SQLULEN
+        timestamp_column_size(self.fraction)
+    }
+    fn decimal_digits(&self) -> ffi::SQLSMALLINT {
+        fraction_digits(self.fraction)
     }
     fn value_ptr(&self) -> ffi::SQLPOINTER {
         self as *const Self as ffi::SQLPOINTER
@@ -640,6 +776,31 @@ unsafe impl<'a> OdbcType<'a> for SqlTimestamp {
     }
 }
 
+/// Number of fractional second digits needed to bind a timestamp with `fraction` nanoseconds
+/// without truncation. Uses milli- or microsecond precision if sufficient, since not all drivers
+/// support nanoseconds.
+// `u32::is_multiple_of` would require Rust 1.87
+#[allow(unknown_lints, clippy::manual_is_multiple_of)]
+pub(super) fn fraction_digits(fraction: ffi::SQLUINTEGER) -> ffi::SQLSMALLINT {
+    if fraction == 0 {
+        0
+    } else if fraction % 1_000_000 == 0 {
+        3
+    } else if fraction % 1_000 == 0 {
+        6
+    } else {
+        9
+    }
+}
+
+/// Column size of a timestamp with `fraction` nanoseconds, i.e. the length of its literal
+pub(super) fn timestamp_column_size(fraction: ffi::SQLUINTEGER) -> ffi::SQLULEN {
+    match fraction_digits(fraction) {
+        0 => 19,
+        digits => 20 + digits as ffi::SQLULEN,
+    }
+}
+
 pub type SqlSsTime2 = ffi::SQL_SS_TIME2_STRUCT;
 
 unsafe impl<'a> OdbcType<'a> for SqlSsTime2 {
@@ -671,6 +832,72 @@ unsafe impl<'a> OdbcType<'a> for SqlSsTime2 {
     }
 }
 
+pub type SqlGuid = ffi::SQLGUID;
+
+/// Column size of `SQL_GUID`, the number of characters of its string form
+/// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`
+const GUID_COLUMN_SIZE: ffi::SQLULEN = 36;
+
+unsafe impl<'a> OdbcType<'a> for SqlGuid {
+    fn sql_data_type() -> ffi::SqlDataType {
+        ffi::SQL_EXT_GUID
+    }
+    fn c_data_type() -> ffi::SqlCDataType {
+        ffi::SQL_C_GUID
+    }
+
+    fn convert(buffer: &'a [u8]) -> Self {
+        assert_eq!(buffer.len(), size_of::<Self>());
+        unsafe {
+            let ptr = buffer.as_ptr() as *const [u8; size_of::<Self>()];
+            transmute(*ptr)
+        }
+    }
+
+    fn column_size(&self) -> ffi::SQLULEN {
+        GUID_COLUMN_SIZE
+    }
+    fn value_ptr(&self) -> ffi::SQLPOINTER {
+        self as *const Self as ffi::SQLPOINTER
+    }
+
+    fn encoded_value(&self) -> EncodedValue {
+        EncodedValue::new(None)
+    }
+}
+
+/// `Uuid` is stored as 16 big endian bytes, while `SQLGUID` stores its first three fields in
+/// native byte order. The value is therefore bound through an `SQLGUID` encoded copy.
+#[cfg(feature = "uuid")]
+unsafe impl<'a> OdbcType<'a> for ::uuid::Uuid {
+    fn sql_data_type() -> ffi::SqlDataType {
+        ffi::SQL_EXT_GUID
+    }
+    fn c_data_type() -> ffi::SqlCDataType {
+        ffi::SQL_C_GUID
+    }
+
+    #[allow(deprecated)]
+    fn convert(buffer: &'a [u8]) -> Self {
+        let guid = SqlGuid::convert(buffer);
+        ::uuid::Uuid::from_fields(guid.d1, guid.d2, guid.d3, &guid.d4)
+    }
+
+    fn column_size(&self) -> ffi::SQLULEN {
+        GUID_COLUMN_SIZE
+    }
+    fn value_ptr(&self) -> ffi::SQLPOINTER {
+        self as *const Self as ffi::SQLPOINTER
+    }
+
+    fn encoded_value(&self) -> EncodedValue {
+        let (d1, d2, d3, d4) = self.as_fields();
+        let guid = SqlGuid { d1, d2, d3, d4: *d4 };
+        let bytes: [u8; size_of::<SqlGuid>()] = unsafe { transmute(guid) };
+        EncodedValue::with_column_size(bytes.to_vec(), GUID_COLUMN_SIZE)
+    }
+}
+
 unsafe impl<'a, T> OdbcType<'a> for Option<T> where T: OdbcType<'a> {
     fn sql_data_type() -> ffi::SqlDataType {
         T::sql_data_type()
@@ -680,6 +907,7 @@ unsafe impl<'a, T> OdbcType<'a> for Option<T> where T: OdbcType<'a> {
         T::c_data_type()
     }
 
+    #[allow(deprecated)]
     fn convert(buffer: &'a [u8]) -> Self {
         Some(T::convert(buffer))
     }
@@ -706,6 +934,40 @@ unsafe impl<'a, T> OdbcType<'a> for Option<T> where T: OdbcType<'a> {
     fn encoded_value(&self) -> EncodedValue {
         EncodedValue::new(None)
     }
+
+    fn convert_with_encoding(
+        buffer: &'a [u8],
+        encoding: &'static Encoding,
+        policy: DecodePolicy,
+    ) -> ::Result<Self> {
+        T::convert_with_encoding(buffer, encoding, policy).map(Some)
+    }
+
+    fn encoded_value_with_encoding(
+        &self,
+        encoding: &'static Encoding,
+    ) -> ::Result<EncodedValue> {
+        match *self {
+            Some(ref t) => t.encoded_value_with_encoding(encoding),
+            None => Ok(EncodedValue::new(None)),
+        }
+    }
+
+    fn bind_types(value: Option<&Self>) -> (ffi::SQLSMALLINT, ffi::SQLSMALLINT) {
+        T::bind_types(value.and_then(Option::as_ref))
+    }
+
+    fn bind_descriptor(&self, stmt: &mut Raii<ffi::Stmt>, parameter_index: u16) -> ::Result<()> {
+        match *self {
+            Some(ref t) => t.bind_descriptor(stmt, parameter_index),
+            // `NULL` values are never converted, so there is nothing to describe
+            None => Ok(()),
+        }
+    }
+
+    fn get_data_target_type(stmt: &mut Raii<ffi::Stmt>, col: u16) -> ::Result<ffi::SQLSMALLINT> {
+        T::get_data_target_type(stmt, col)
+    }
 }
 
 
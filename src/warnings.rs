@@ -0,0 +1,147 @@
+//! Collects warnings reported along with `SQL_SUCCESS_WITH_INFO`
+use super::{DiagnosticRecord, GetDiagRec};
+use std::fmt;
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Callback invoked with every warning reported by a connection or its statements, e.g. to route
+/// them to telemetry. Set it with `Connection::set_warning_handler`.
+pub type WarningHandler = Arc<dyn Fn(&DiagnosticRecord) + Send + Sync>;
+
+/// Number of warnings kept per handle. Applications which never take them would otherwise leak
+/// memory, e.g. on a statement executed over and over again.
+pub const MAX_KEPT_WARNINGS: usize = 256;
+
+/// Warnings reported for a handle, which have not been taken by the application yet
+pub struct Warnings {
+    records: Mutex<Vec<DiagnosticRecord>>,
+    handler: Mutex<Option<WarningHandler>>,
+}
+
+impl fmt::Debug for Warnings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Warnings")
+            .field("records", &*lock(&self.records))
+            .field("handler", &lock(&self.handler).is_some())
+            .finish()
+    }
+}
+
+impl Warnings {
+    pub(crate) fn new(handler: Option<WarningHandler>) -> Warnings {
+        Warnings {
+            records: Mutex::new(Vec::new()),
+            handler: Mutex::new(handler),
+        }
+    }
+
+    /// Passes `record` to the handler and keeps it until taken, unless `MAX_KEPT_WARNINGS` are
+    /// kept already
+    pub(crate) fn push(&self, record: DiagnosticRecord) {
+        // Do not hold the lock while calling out, the handler may well set another handler
+        let handler = self.handler();
+        if let Some(handler) = handler {
+            handler(&record);
+        }
+        let mut records = lock(&self.records);
+        if records.len() < MAX_KEPT_WARNINGS {
+            records.push(record);
+        }
+    }
+
+    pub(crate) fn take(&self) -> Vec<DiagnosticRecord> {
+        mem::take(&mut *lock(&self.records))
+    }
+
+    pub(crate) fn handler(&self) -> Option<WarningHandler> {
+        lock(&self.handler).clone()
+    }
+
+    pub(crate) fn set_handler(&self, handler: Option<WarningHandler>) {
+        *lock(&self.handler) = handler;
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // Neither records nor handler can be left inconsistent by a panic
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Implemented by handles which keep the warnings reported for them
+pub trait HasWarnings {
+    /// `None` if warnings reported for the handle are only logged
+    fn warnings(&self) -> Option<&Warnings>;
+}
+
+/// Logs the diagnostic records of `odbc_object` after a call returned `SQL_SUCCESS_WITH_INFO` and
+/// keeps them, if the handle does so.
+pub(crate) fn collect_warnings<O: GetDiagRec + HasWarnings>(odbc_object: &O) {
+    let mut i = 1;
+    while let Some(diag) = odbc_object.get_diag_rec(i) {
+        warn!("{}", diag);
+        if let Some(warnings) = odbc_object.warnings() {
+            warnings.push(diag);
+        }
+        i += 1;
+    }
+}
+
+/// Diagnostics of a handle, with warnings kept in `warnings`. Used for handles which do not have
+/// an owner keeping them yet, e.g. while connecting.
+pub(crate) struct Collecting<'a, D: 'a> {
+    pub(crate) diag: &'a D,
+    pub(crate) warnings: &'a Warnings,
+}
+
+impl<'a, D: GetDiagRec> GetDiagRec for Collecting<'a, D> {
+    fn get_diag_rec(&self, record_number: i16) -> Option<DiagnosticRecord> {
+        self.diag.get_diag_rec(record_number)
+    }
+}
+
+impl<'a, D> HasWarnings for Collecting<'a, D> {
+    fn warnings(&self) -> Option<&Warnings> {
+        Some(self.warnings)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn handler_sees_all_warnings() {
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = seen.clone();
+        let warnings = Warnings::new(Some(Arc::new(move |_: &DiagnosticRecord| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })));
+        warnings.push(DiagnosticRecord::with_message(b"01004", "String data, right truncated"));
+        warnings.push(DiagnosticRecord::with_message(b"01000", "Changed database context"));
+        assert_eq!(seen.load(Ordering::SeqCst), 2);
+        let taken = warnings.take();
+        assert_eq!(taken.len(), 2);
+        assert_eq!(taken[0].sql_state(), "01004");
+        assert!(warnings.take().is_empty());
+
+        warnings.set_handler(None);
+        warnings.push(DiagnosticRecord::empty());
+        assert_eq!(seen.load(Ordering::SeqCst), 2);
+        assert_eq!(warnings.take().len(), 1);
+    }
+
+    #[test]
+    fn kept_warnings_are_limited() {
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = seen.clone();
+        let warnings = Warnings::new(Some(Arc::new(move |_: &DiagnosticRecord| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })));
+        for _ in 0..MAX_KEPT_WARNINGS + 10 {
+            warnings.push(DiagnosticRecord::with_message(b"01000", "Changed database context"));
+        }
+        assert_eq!(seen.load(Ordering::SeqCst), MAX_KEPT_WARNINGS + 10);
+        assert_eq!(warnings.take().len(), MAX_KEPT_WARNINGS);
+    }
+}
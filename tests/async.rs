@@ -0,0 +1,87 @@
+//! Tests for the asynchronous API running on tokio's blocking thread pool
+#![cfg(feature = "async")]
+extern crate futures_util;
+extern crate odbc;
+extern crate tokio;
+
+use futures_util::StreamExt;
+use odbc::*;
+use std::sync::Arc;
+use tokio::runtime::{Builder, Runtime};
+
+const CONNECTION_STRING: &str = "dsn=TestDataSource;Uid=;Pwd=;";
+
+fn runtime() -> Runtime {
+    Builder::new_current_thread().build().unwrap()
+}
+
+fn connect(runtime: &Runtime) -> AsyncConnection {
+    let env = Arc::new(create_environment_v3().unwrap());
+    runtime.block_on(AsyncConnection::connect(&env, CONNECTION_STRING)).unwrap()
+}
+
+#[test]
+fn async_fetch_batch() {
+    let runtime = runtime();
+    let conn = connect(&runtime);
+    let stmt = runtime
+        .block_on(conn.execute("SELECT TITLE FROM MOVIES ORDER BY YEAR"))
+        .unwrap();
+    assert!(stmt.has_result());
+
+    let title = |cursor: &mut Cursor<Executed, _>| Ok(cursor.get_data::<String>(1)?.unwrap());
+    let first = runtime.block_on(stmt.fetch_batch(2, title)).unwrap();
+    assert_eq!(first, vec!["2001: A Space Odyssey", "Jurassic Park"]);
+    let rest = runtime.block_on(stmt.fetch_batch(10, title)).unwrap();
+    assert_eq!(rest, vec!["Interstellar"]);
+    assert!(runtime.block_on(stmt.fetch_batch(10, title)).unwrap().is_empty());
+}
+
+#[test]
+fn async_row_stream() {
+    let runtime = runtime();
+    let conn = connect(&runtime);
+    let stmt = runtime
+        .block_on(conn.execute("SELECT YEAR FROM MOVIES ORDER BY YEAR"))
+        .unwrap();
+    let years: Vec<i32> = runtime
+        .block_on(
+            stmt.rows(2, |cursor| Ok(cursor.get_data::<i32>(1)?.unwrap()))
+                .collect::<Vec<_>>(),
+        )
+        .into_iter()
+        .collect::<Result<_>>()
+        .unwrap();
+    assert_eq!(years, vec![1968, 1993, 2014]);
+}
+
+#[test]
+fn async_statement_without_result_set() {
+    let runtime = runtime();
+    let conn = connect(&runtime);
+    let stmt = runtime
+        .block_on(conn.execute("UPDATE MOVIES SET YEAR = YEAR WHERE YEAR > 2000"))
+        .unwrap();
+    assert!(!stmt.has_result());
+    assert_eq!(stmt.affected_row_count(), 1);
+    let rows = runtime.block_on(stmt.rows(10, |_| Ok(())).collect::<Vec<_>>());
+    assert!(rows.is_empty());
+}
+
+#[test]
+fn async_execution_error() {
+    let runtime = runtime();
+    let conn = connect(&runtime);
+    let error = runtime.block_on(conn.execute("SELECT * FROM NOT_A_TABLE")).err().unwrap();
+    assert!(error.to_string().contains("no such table"));
+}
+
+#[test]
+fn async_drop_pending_execution() {
+    let runtime = runtime();
+    let conn = connect(&runtime);
+    // Dropping the future before it has been polled must not leak or break the connection
+    drop(conn.execute("SELECT TITLE FROM MOVIES"));
+    let stmt = runtime.block_on(conn.execute("SELECT TITLE FROM MOVIES")).unwrap();
+    assert!(stmt.has_result());
+}
@@ -0,0 +1,64 @@
+//! Tests for the `chrono` and `time` integrations
+extern crate odbc;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "time")]
+extern crate time;
+
+#[cfg(feature = "chrono")]
+#[test]
+fn chrono_round_trip() {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+    use odbc::*;
+
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let date = NaiveDate::from_ymd_opt(2021, 3, 14).unwrap();
+    let time = NaiveTime::from_hms_opt(15, 9, 26).unwrap();
+    let datetime = date.and_hms_micro_opt(15, 9, 26, 535_897).unwrap();
+    let utc = DateTime::<Utc>::from_naive_utc_and_offset(datetime, Utc);
+
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let stmt = stmt.bind_parameter(1, &date).unwrap();
+    let stmt = stmt.bind_parameter(2, &time).unwrap();
+    let stmt = stmt.bind_parameter(3, &datetime).unwrap();
+    let stmt = stmt.bind_parameter(4, &utc).unwrap();
+    if let Data(mut stmt) = stmt.exec_direct("SELECT ?, ?, ?, ?").unwrap() {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        assert_eq!(cursor.get_data::<NaiveDate>(1).unwrap(), Some(date));
+        assert_eq!(cursor.get_data::<NaiveTime>(2).unwrap(), Some(time));
+        assert_eq!(cursor.get_data::<NaiveDateTime>(3).unwrap(), Some(datetime));
+        assert_eq!(cursor.get_data::<DateTime<Utc>>(4).unwrap(), Some(utc));
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn time_round_trip() {
+    use odbc::*;
+    use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let date = Date::from_calendar_date(2021, Month::March, 14).unwrap();
+    let time = Time::from_hms(15, 9, 26).unwrap();
+    let datetime = PrimitiveDateTime::new(date, Time::from_hms_micro(15, 9, 26, 535_897).unwrap());
+    let utc = datetime.assume_utc();
+
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let stmt = stmt.bind_parameter(1, &date).unwrap();
+    let stmt = stmt.bind_parameter(2, &time).unwrap();
+    let stmt = stmt.bind_parameter(3, &datetime).unwrap();
+    let stmt = stmt.bind_parameter(4, &utc).unwrap();
+    if let Data(mut stmt) = stmt.exec_direct("SELECT ?, ?, ?, ?").unwrap() {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        assert_eq!(cursor.get_data::<Date>(1).unwrap(), Some(date));
+        assert_eq!(cursor.get_data::<Time>(2).unwrap(), Some(time));
+        assert_eq!(cursor.get_data::<PrimitiveDateTime>(3).unwrap(), Some(datetime));
+        assert_eq!(cursor.get_data::<OffsetDateTime>(4).unwrap(), Some(utc));
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+}
@@ -0,0 +1,89 @@
+//! Tests for decoding character data according to the `DecodePolicy` of a connection
+extern crate odbc;
+
+use odbc::*;
+use odbc::encoding_rs::{UTF_8, WINDOWS_1252};
+use std::borrow::Cow;
+use std::error::Error;
+
+fn connect<'env>(
+    env: &'env Environment<Version3>,
+    db: &'static encoding_rs::Encoding,
+    decode: DecodePolicy,
+) -> Connection<'env, safe::AutocommitOn> {
+    let mut conn = env.connect("TestDataSource", "", "").unwrap();
+    conn.set_encodings(Encodings { db, decode, ..Encodings::default() });
+    conn
+}
+
+/// Executes `query` and retrieves the first column as `String`
+fn select(conn: &Connection<safe::AutocommitOn>, query: &str) -> Result<Option<String>> {
+    let stmt = Statement::with_parent(conn).unwrap();
+    match stmt.exec_direct(query).unwrap() {
+        Data(mut stmt) => {
+            let mut cursor = stmt.fetch().unwrap().unwrap();
+            cursor.get_data::<String>(1)
+        }
+        NoData(_) => panic!("SELECT did not return result set"),
+    }
+}
+
+// `X'C328'` is not valid UTF-8
+const MALFORMED: &str = "SELECT CAST(X'C328' AS TEXT)";
+
+#[test]
+fn lossy_replaces_malformed_data() {
+    let env = create_environment_v3().unwrap();
+    let conn = connect(&env, UTF_8, DecodePolicy::Lossy);
+    assert_eq!(select(&conn, MALFORMED).unwrap().unwrap(), "\u{FFFD}(");
+}
+
+#[test]
+fn strict_rejects_malformed_data() {
+    let env = create_environment_v3().unwrap();
+    let conn = connect(&env, UTF_8, DecodePolicy::Strict);
+    let error = select(&conn, MALFORMED).unwrap_err();
+    assert_eq!(error.get_raw_state(), b"22018\0");
+    let decode_error = error.decode_error().unwrap();
+    assert_eq!(decode_error.kind(), DecodeErrorKind::Malformed);
+    assert_eq!(decode_error.bytes(), &[0xC3, 0x28]);
+    assert!(error.source().is_some());
+    // Valid data is decoded as usual
+    assert_eq!(select(&conn, "SELECT 'é'").unwrap().unwrap(), "é");
+}
+
+#[test]
+fn raw_passes_data_through() {
+    let env = create_environment_v3().unwrap();
+    let lossy = connect(&env, WINDOWS_1252, DecodePolicy::Lossy);
+    let raw = connect(&env, WINDOWS_1252, DecodePolicy::Raw);
+    // The statement text is encoded as windows-1252, so 'é' is sent as a single byte which is not
+    // valid UTF-8. A UTF-8 literal is used instead.
+    let query = "SELECT CAST(X'C3A9' AS TEXT)";
+    assert_eq!(select(&lossy, query).unwrap().unwrap(), "Ã©");
+    assert_eq!(select(&raw, query).unwrap().unwrap(), "é");
+    let stmt = Statement::with_parent(&raw).unwrap();
+    match stmt.exec_direct(MALFORMED).unwrap() {
+        Data(mut stmt) => {
+            let mut cursor = stmt.fetch().unwrap().unwrap();
+            let error = cursor.get_data::<Cow<str>>(1).unwrap_err();
+            assert_eq!(error.decode_error().unwrap().bytes(), &[0xC3, 0x28]);
+        }
+        NoData(_) => panic!("SELECT did not return result set"),
+    };
+}
+
+#[test]
+fn str_requiring_allocation_is_an_error() {
+    let env = create_environment_v3().unwrap();
+    let conn = connect(&env, WINDOWS_1252, DecodePolicy::Lossy);
+    let stmt = Statement::with_parent(&conn).unwrap();
+    match stmt.exec_direct("SELECT 'é'").unwrap() {
+        Data(mut stmt) => {
+            let mut cursor = stmt.fetch().unwrap().unwrap();
+            let error = cursor.get_data::<&str>(1).unwrap_err();
+            assert_eq!(error.decode_error().unwrap().kind(), DecodeErrorKind::NotBorrowable);
+        }
+        NoData(_) => panic!("SELECT did not return result set"),
+    };
+}
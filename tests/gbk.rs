@@ -180,3 +180,41 @@ fn prepare_2() {
         panic!("SELECT did not return result set");
     };
 }
+
+#[test]
+fn encodings_from_labels() {
+    let encodings = Encodings::for_labels("utf-8", "gbk").unwrap();
+    assert_eq!(encodings.os, encoding_rs::UTF_8);
+    assert_eq!(encodings.db, encoding_rs::GBK);
+    assert!(Encodings::for_labels("utf-8", "not an encoding").is_none());
+    assert_eq!(Encodings::default().db, encoding_rs::UTF_8);
+}
+
+#[test]
+fn encodings_per_connection() {
+    let env = create_environment_v3().unwrap();
+    let utf8 = env.connect("TestDataSource", "", "").unwrap();
+    let mut latin1 = env.connect("TestDataSource", "", "").unwrap();
+    latin1.set_encodings(Encodings {
+        os: encoding_rs::UTF_8,
+        db: encoding_rs::WINDOWS_1252,
+        ..Encodings::default()
+    });
+
+    let select = |conn: &Connection<safe::AutocommitOn>| -> (String, Vec<u8>) {
+        let stmt = Statement::with_parent(conn).unwrap();
+        match stmt.exec_direct("SELECT 'é', 'é'").unwrap() {
+            Data(mut stmt) => {
+                let mut cursor = stmt.fetch().unwrap().unwrap();
+                let text = cursor.get_data::<String>(1).unwrap().unwrap();
+                let bytes = cursor.get_data::<Vec<u8>>(2).unwrap().unwrap();
+                (text, bytes)
+            }
+            NoData(_) => panic!("SELECT did not return result set"),
+        }
+    };
+
+    // Both connections round trip the text, but send different bytes to the driver
+    assert_eq!(select(&utf8), ("é".to_owned(), vec![0xC3, 0xA9]));
+    assert_eq!(select(&latin1), ("é".to_owned(), vec![0xE9]));
+}
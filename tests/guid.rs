@@ -0,0 +1,57 @@
+//! Tests for GUID values bound and fetched as `SQL_C_GUID`
+extern crate odbc;
+#[cfg(feature = "uuid")]
+extern crate uuid;
+
+use odbc::*;
+
+#[test]
+fn guid_round_trip() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let guid = SqlGuid {
+        d1: 0x67e5_5044,
+        d2: 0x10b1,
+        d3: 0x426f,
+        d4: [0x92, 0x47, 0xbb, 0x68, 0x0e, 0x5f, 0xe0, 0xc8],
+    };
+    let stmt = stmt.bind_parameter(1, &guid).unwrap();
+    if let Data(mut stmt) = stmt.exec_direct("SELECT ?").unwrap() {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        assert_eq!(cursor.get_data::<SqlGuid>(1).unwrap(), Some(guid));
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+}
+
+#[test]
+fn guid_null() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    if let Data(mut stmt) = stmt.exec_direct("SELECT NULL").unwrap() {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        assert_eq!(cursor.get_data::<SqlGuid>(1).unwrap(), None);
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn guid_uuid() {
+    use uuid::Uuid;
+
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let uuid = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+    let stmt = stmt.bind_parameter(1, &uuid).unwrap();
+    if let Data(mut stmt) = stmt.exec_direct("SELECT ?").unwrap() {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        assert_eq!(cursor.get_data::<Uuid>(1).unwrap(), Some(uuid));
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+}
@@ -123,3 +123,34 @@ fn _f64() {
     let param: f64 = 3.14;
     test_type!(C, &param)
 }
+
+#[test]
+fn _explicit_sql_type() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let param = "Hello, World!";
+    let stmt = stmt
+        .bind(Parameter::new(1, &param).sql_type(ffi::SQL_VARCHAR).column_size(50))
+        .unwrap();
+    if let Data(mut stmt) = stmt.exec_direct(A).unwrap() {
+        assert!(stmt.fetch().unwrap().is_some());
+    } else {
+        panic!("SELECT did not return result set");
+    };
+}
+
+#[test]
+fn _described_sql_type() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap().prepare(B).unwrap();
+    let param = 42i64;
+    // Falls back to the type of the value, if the driver can not describe the parameter
+    let stmt = stmt.bind(Parameter::new(1, &param).auto()).unwrap();
+    if let Data(mut stmt) = stmt.execute().unwrap() {
+        assert!(stmt.fetch().unwrap().is_some());
+    } else {
+        panic!("SELECT did not return result set");
+    };
+}
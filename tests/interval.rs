@@ -0,0 +1,61 @@
+//! Tests for intervals bound and fetched as `SQL_INTERVAL_STRUCT`
+extern crate odbc;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+
+use odbc::*;
+use std::time::Duration;
+
+fn round_trip(interval: &SqlInterval) -> Option<SqlInterval> {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let stmt = stmt.bind_parameter(1, interval).unwrap();
+    let fetched = if let Data(mut stmt) = stmt.exec_direct("SELECT ?").unwrap() {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        cursor.get_data::<SqlInterval>(1).unwrap()
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+    fetched
+}
+
+#[test]
+fn interval_day_to_second() {
+    let interval = SqlInterval::from_duration(Duration::from_micros(93_784_000_005)).unwrap();
+    let fetched = round_trip(&interval).unwrap();
+    assert_eq!(fetched.to_duration(), Some(Duration::from_micros(93_784_000_005)));
+}
+
+#[test]
+fn interval_year_to_month() {
+    let interval = SqlInterval::year_month(SqlIntervalType::SQL_IS_YEAR_TO_MONTH, true, 1, 6);
+    let fetched = round_trip(&interval).unwrap();
+    assert_eq!(fetched.total_months(), Some(-18));
+}
+
+#[test]
+fn interval_null() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    if let Data(mut stmt) = stmt.exec_direct("SELECT NULL").unwrap() {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        assert_eq!(cursor.get_data::<SqlInterval>(1).unwrap(), None);
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn interval_chrono() {
+    use std::convert::TryFrom;
+
+    let duration = chrono::Duration::minutes(-90);
+    let interval = SqlInterval::try_from(duration).unwrap();
+    assert!(interval.is_negative());
+    assert_eq!(chrono::Duration::try_from(interval).unwrap(), duration);
+    let year_month = SqlInterval::year_month(SqlIntervalType::SQL_IS_YEAR, false, 1, 0);
+    assert_eq!(chrono::Duration::try_from(year_month), Err(IntervalRangeError));
+}
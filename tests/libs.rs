@@ -3,6 +3,7 @@ extern crate odbc_safe;
 
 use odbc::*;
 use odbc_safe::AutocommitOn;
+use std::sync::Arc;
 
 #[test]
 fn list_tables() {
@@ -59,9 +60,10 @@ fn invalid_connection_string() {
     };
 
     let environment = create_environment_v3().unwrap();
-    let result = environment.connect_with_connection_string("bla");
-    let message = format!("{}", result.err().unwrap());
-    assert_eq!(expected, message);
+    let error = environment.connect_with_connection_string("bla").err().unwrap();
+    assert_eq!(error.function(), Some("SQLDriverConnect"));
+    assert_eq!(expected, format!("{}", error.record()));
+    assert!(format!("{}", error).starts_with("SQLDriverConnect failed: State: IM002"));
 }
 
 #[test]
@@ -74,6 +76,33 @@ fn test_connection_string() {
     conn.disconnect().unwrap();
 }
 
+#[test]
+fn shared_statement() {
+    fn assert_static_send<T: Send + 'static>(_: &T) {}
+
+    let env = Arc::new(create_environment_v3().unwrap());
+    let conn = Arc::new(Environment::connect_shared(&env, "TestDataSource", "", "").unwrap());
+    let stmt = Statement::with_shared_parent(&conn).unwrap();
+    assert_static_send(&stmt);
+    // The statement keeps connection and environment alive
+    drop(conn);
+    drop(env);
+
+    if let Data(mut stmt) = std::thread::spawn(move || {
+        stmt.exec_direct("SELECT TITLE FROM MOVIES ORDER BY YEAR").unwrap()
+    }).join()
+        .unwrap()
+    {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        assert_eq!(
+            cursor.get_data::<String>(1).unwrap().unwrap(),
+            "2001: A Space Odyssey"
+        );
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+}
+
 #[test]
 fn test_direct_select() {
     let env = create_environment_v3().unwrap();
@@ -199,6 +228,99 @@ fn prepared_execution() {
     execute_query(1993, "Jurassic Park", stmt).unwrap();
 }
 
+#[test]
+fn statement_cache() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    conn.set_statement_cache_capacity(2);
+    let sql = "SELECT TITLE FROM MOVIES WHERE YEAR = ?";
+    for &(year, expected) in &[(1968, "2001: A Space Odyssey"), (1993, "Jurassic Park")] {
+        let stmt = conn.prepare_cached(sql).unwrap();
+        // Checked out statements are not part of the cache
+        assert_eq!(conn.cached_statement_count(), 0);
+        let stmt = stmt.bind_parameter(1, &year).unwrap();
+        // Dropped with an open cursor, which is closed once the statement is handed back
+        if let Data(mut stmt) = stmt.execute().unwrap() {
+            let mut cursor = stmt.fetch().unwrap().unwrap();
+            assert_eq!(cursor.get_data::<String>(1).unwrap().unwrap(), expected);
+        } else {
+            panic!("SELECT statement returned no result set");
+        }
+        assert_eq!(conn.cached_statement_count(), 1);
+    }
+
+    for sql in &["SELECT 1", "SELECT 2", "SELECT 3"] {
+        conn.prepare_cached(sql).unwrap();
+    }
+    assert_eq!(conn.cached_statement_count(), 2);
+    conn.set_statement_cache_capacity(0);
+    assert_eq!(conn.cached_statement_count(), 0);
+}
+
+#[test]
+fn execute_script() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let script = "CREATE TEMPORARY TABLE SCRIPT (A TEXT);\n\
+                  -- Two rows\n\
+                  INSERT INTO SCRIPT VALUES ('a;b'), ('c');\n\
+                  SELECT A FROM SCRIPT;\n";
+    let outcomes = conn.execute_script(script).unwrap();
+    assert_eq!(outcomes.iter().map(|o| o.line).collect::<Vec<_>>(), vec![1, 3, 4]);
+    assert_eq!(outcomes[1].row_count, 2);
+    assert_eq!(outcomes[2].row_count, 2);
+
+    let error = conn.execute_script("DELETE FROM SCRIPT;\nSELECT * FROM NO_SUCH_TABLE;\nSELECT 1;")
+        .unwrap_err();
+    assert_eq!(error.line, 2);
+    assert_eq!(error.sql, "SELECT * FROM NO_SUCH_TABLE");
+    assert_eq!(error.error.function(), Some("SQLExecDirect"));
+    assert!(!error.error.records().is_empty());
+    assert_eq!(error.executed.len(), 1);
+}
+
+#[test]
+fn native_sql() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let sql = "SELECT TITLE FROM MOVIES WHERE YEAR = 1968";
+    assert_eq!(conn.native_sql(sql).unwrap(), sql);
+}
+
+#[test]
+fn column_attributes() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let stmt = match stmt.exec_direct("SELECT TITLE AS NAME, YEAR FROM MOVIES").unwrap() {
+        Data(stmt) => stmt,
+        NoData(_) => panic!("SELECT statement did not return result set!"),
+    };
+
+    let label = stmt.col_attribute(1, ColumnAttribute::Label).unwrap();
+    assert_eq!(label.as_str(), Some("NAME"));
+    let attributes = stmt.col_attributes(2).unwrap();
+    assert_eq!(attributes.label, "YEAR");
+    assert_eq!(attributes.base_column_name, "YEAR");
+    assert_eq!(attributes.table_name, "MOVIES");
+    assert!(attributes.display_size > 0);
+}
+
+#[test]
+fn parameter_metadata() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let stmt = stmt.prepare("SELECT TITLE FROM MOVIES WHERE YEAR = ? AND TITLE = ?")
+        .unwrap();
+    assert_eq!(stmt.num_params().unwrap(), 2);
+    match stmt.describe_param(1) {
+        Ok(descriptor) => assert_ne!(descriptor.data_type, ffi::SQL_UNKNOWN_TYPE),
+        // Driver does not support `SQLDescribeParam`
+        Err(error) => assert!(error.get_raw_state() == b"HYC00\0" || error.get_raw_state() == b"IM001\0"),
+    }
+}
+
 // These tests query the results of catalog functions. These results are only likely to match the
 // expectation on the travis ci build on linux. Therefore we limit compilation and execution of
 // these tests to this platform.
@@ -351,3 +473,27 @@ fn zero_truncation_bug() {
         panic!("SELECT statement returned no result set")
     };
 }
+
+#[test]
+fn take_warnings() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let reported = Arc::new(AtomicUsize::new(0));
+    let counter = reported.clone();
+    conn.set_warning_handler(Some(Arc::new(move |_: &DiagnosticRecord| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    })));
+    conn.take_warnings();
+
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let stmt = match stmt.exec_direct("SELECT TITLE FROM MOVIES").unwrap() {
+        Data(stmt) => stmt,
+        NoData(_) => panic!("SELECT statement did not return result set!"),
+    };
+    // Every warning reported to the handler is kept as well, until taken once
+    let warnings = stmt.take_warnings();
+    assert_eq!(warnings.len() + conn.take_warnings().len(), reported.load(Ordering::SeqCst));
+    assert!(stmt.take_warnings().is_empty());
+}
@@ -0,0 +1,88 @@
+//! Tests for exact numeric values bound and fetched as `SQL_C_NUMERIC`
+extern crate odbc;
+#[cfg(feature = "bigdecimal")]
+extern crate bigdecimal;
+#[cfg(feature = "rust_decimal")]
+extern crate rust_decimal;
+
+use odbc::*;
+
+#[test]
+fn numeric_round_trip() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+
+    let stmt = match stmt.exec_direct("CREATE TABLE NUMERIC_ROUND_TRIP (AMOUNT DECIMAL(10,2))").unwrap() {
+        Data(stmt) => stmt.close_cursor().unwrap(),
+        NoData(stmt) => stmt,
+    };
+    let amount = SqlNumeric::new(-1234567, 10, 2);
+    stmt
+        .prepare("INSERT INTO NUMERIC_ROUND_TRIP VALUES (?)")
+        .unwrap()
+        .bind_parameter(1, &amount)
+        .unwrap()
+        .execute()
+        .unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    if let Data(mut stmt) = stmt.exec_direct("SELECT AMOUNT FROM NUMERIC_ROUND_TRIP").unwrap() {
+        {
+            let mut cursor = stmt.fetch().unwrap().unwrap();
+            let fetched = cursor.get_data::<SqlNumeric>(1).unwrap().unwrap();
+            assert_eq!(fetched.to_string(), "-12345.67");
+            assert_eq!(fetched.mantissa(), Some(-1234567));
+            assert_eq!(fetched.scale, 2);
+        }
+        let stmt = stmt.close_cursor().unwrap();
+        stmt.exec_direct("DROP TABLE NUMERIC_ROUND_TRIP").unwrap();
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+}
+
+#[test]
+fn numeric_null() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    if let Data(mut stmt) = stmt.exec_direct("SELECT CAST(NULL AS DECIMAL(10,2))").unwrap() {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        assert_eq!(cursor.get_data::<SqlNumeric>(1).unwrap(), None);
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+}
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn numeric_rust_decimal() {
+    use rust_decimal::Decimal;
+    use std::convert::TryFrom;
+    use std::str::FromStr;
+
+    let decimal = Decimal::from_str("-79228162514264337593543950.335").unwrap();
+    let numeric = SqlNumeric::from(decimal);
+    assert_eq!(numeric.to_string(), "-79228162514264337593543950.335");
+    assert_eq!(Decimal::try_from(numeric), Ok(decimal));
+    // Negative scales are applied to the mantissa
+    assert_eq!(Decimal::try_from(SqlNumeric::new(42, 10, -3)), Ok(Decimal::from(42000)));
+    // Exceeds the 96 bit mantissa of `Decimal`
+    assert!(Decimal::try_from(SqlNumeric::new(i128::MAX, 38, 0)).is_err());
+}
+
+#[cfg(feature = "bigdecimal")]
+#[test]
+fn numeric_bigdecimal() {
+    use bigdecimal::BigDecimal;
+    use std::convert::TryFrom;
+    use std::str::FromStr;
+
+    let big = BigDecimal::from_str("12345678901234567890123456789.012345678").unwrap();
+    let numeric = SqlNumeric::try_from(big.clone()).unwrap();
+    assert_eq!(numeric.to_string(), "12345678901234567890123456789.012345678");
+    assert_eq!(BigDecimal::from(numeric), big);
+    // Does not fit into 128 bits
+    let huge = BigDecimal::from_str("1e40").unwrap() + BigDecimal::from(1);
+    assert_eq!(SqlNumeric::try_from(huge), Err(NumericRangeError));
+}
@@ -0,0 +1,82 @@
+//! Tests for binding all parameters of a statement at once and binding by name
+extern crate odbc;
+
+use odbc::*;
+
+struct MovieQuery {
+    title: String,
+    year: u16,
+}
+
+impl_params!(MovieQuery { title, year });
+
+#[test]
+fn bind_tuple() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let stmt = stmt.prepare("SELECT TITLE FROM MOVIES WHERE YEAR > ? AND YEAR < ?")
+        .unwrap();
+    if let Data(mut stmt) = stmt.execute_with(&(1967, 1969)).unwrap() {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        assert_eq!(cursor.get_data::<String>(1).unwrap().unwrap(), "2001: A Space Odyssey");
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+}
+
+#[test]
+fn bind_struct() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let stmt = stmt.prepare("SELECT YEAR FROM MOVIES WHERE TITLE = ? AND YEAR = ?")
+        .unwrap();
+    let query = MovieQuery {
+        title: "Jurassic Park".to_owned(),
+        year: 1993,
+    };
+    if let Data(mut stmt) = stmt.execute_with(&query).unwrap() {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        assert_eq!(cursor.get_data::<u16>(1).unwrap(), Some(1993));
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+}
+
+#[test]
+fn bind_params_count_mismatch() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let stmt = stmt.prepare("SELECT TITLE FROM MOVIES WHERE YEAR = ?").unwrap();
+    let error = stmt.bind_params(&(1968, 1993)).err().unwrap();
+    assert_eq!(error.get_raw_state(), b"07002\0");
+}
+
+#[test]
+fn bind_named() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let named = NamedSql::parse("SELECT TITLE FROM MOVIES WHERE YEAR > :year - 1 AND YEAR < :year + 1");
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let stmt = stmt.prepare(named.sql()).unwrap();
+    let year = 1968;
+    let stmt = stmt.bind_named(&named, "year", &year).unwrap();
+    if let Data(mut stmt) = stmt.execute().unwrap() {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        assert_eq!(cursor.get_data::<String>(1).unwrap().unwrap(), "2001: A Space Odyssey");
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+}
+
+#[test]
+fn bind_unknown_name() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let named = NamedSql::parse("SELECT TITLE FROM MOVIES WHERE YEAR = :year");
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let error = stmt.bind_named(&named, "title", &"Jurassic Park").err().unwrap();
+    assert_eq!(error.get_raw_state(), b"07009\0");
+}
@@ -0,0 +1,82 @@
+//! Tests for native asynchronous execution using the asynchronous mode of the driver
+extern crate futures_executor;
+extern crate odbc;
+
+use futures_executor::block_on;
+use odbc::*;
+
+#[test]
+fn exec_direct_async() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let query = stmt.exec_direct_async("SELECT TITLE FROM MOVIES ORDER BY YEAR");
+    let mut stmt = match block_on(query).unwrap() {
+        Data(stmt) => stmt,
+        NoData(_) => panic!("SELECT statement returned no result set"),
+    };
+    let mut titles = Vec::new();
+    while let Some(mut cursor) = block_on(stmt.fetch_async()).unwrap() {
+        titles.push(cursor.get_data::<String>(1).unwrap().unwrap());
+    }
+    assert_eq!(titles, vec!["2001: A Space Odyssey", "Jurassic Park", "Interstellar"]);
+
+    // The statement can still be used with the blocking API
+    let stmt = stmt.close_cursor().unwrap();
+    match stmt.exec_direct("SELECT YEAR FROM MOVIES").unwrap() {
+        Data(mut stmt) => assert!(stmt.fetch().unwrap().is_some()),
+        NoData(_) => panic!("SELECT statement returned no result set"),
+    };
+}
+
+#[test]
+fn execute_async_prepared() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let stmt = stmt.prepare("SELECT TITLE FROM MOVIES WHERE YEAR = ?").unwrap();
+    let year = 1993;
+    let stmt = stmt.bind_parameter(1, &year).unwrap();
+    match block_on(stmt.execute_async()).unwrap() {
+        Data(mut stmt) => {
+            let mut cursor = block_on(stmt.fetch_async()).unwrap().unwrap();
+            assert_eq!(cursor.get_data::<String>(1).unwrap().unwrap(), "Jurassic Park");
+        }
+        NoData(_) => panic!("SELECT statement returned no result set"),
+    };
+}
+
+#[test]
+fn exec_direct_async_error() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let error = block_on(stmt.exec_direct_async("SELECT * FROM NOT_A_TABLE")).err().unwrap();
+    assert!(error.to_string().contains("no such table"));
+}
+
+#[test]
+fn fetch_async_error() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    // Only the second row overflows, so the error is reported by the fetch rather than execution
+    let query = "SELECT abs(X) FROM (SELECT 1 AS X UNION ALL SELECT -9223372036854775807 - 1)";
+    let mut stmt = match block_on(stmt.exec_direct_async(query)).unwrap() {
+        Data(stmt) => stmt,
+        NoData(_) => panic!("SELECT statement returned no result set"),
+    };
+    assert!(block_on(stmt.fetch_async()).unwrap().is_some());
+    let error = block_on(stmt.fetch_async()).err().unwrap();
+    assert_eq!(error.get_raw_state(), b"HY000\0");
+    assert!(error.to_string().contains("integer overflow"));
+}
+
+#[test]
+fn connect_async() {
+    let env = create_environment_v3().unwrap();
+    let conn = block_on(env.connect_with_connection_string_async("dsn=TestDataSource;Uid=;Pwd=;"))
+        .unwrap();
+    assert!(!conn.is_connection_dead().unwrap());
+    assert!(block_on(env.connect_with_connection_string_async("bla")).is_err());
+}
@@ -0,0 +1,159 @@
+extern crate odbc;
+#[cfg(feature = "r2d2")]
+extern crate r2d2;
+
+use odbc::*;
+use std::time::Duration;
+
+const CONNECTION_STRING: &str = "dsn=TestDataSource;Uid=;Pwd=;";
+
+#[test]
+fn reuse_pooled_connection() {
+    let env = create_environment_v3().unwrap();
+    let pool = Pool::new(env, CONNECTION_STRING);
+    assert_eq!(pool.open_connections(), 0);
+
+    for _ in 0..3 {
+        let conn = pool.get().unwrap();
+        let stmt = Statement::with_parent(&conn).unwrap();
+        if let Data(mut stmt) = stmt.exec_direct("SELECT TITLE FROM MOVIES WHERE YEAR = 1968").unwrap() {
+            let mut cursor = stmt.fetch().unwrap().unwrap();
+            assert_eq!(
+                cursor.get_data::<String>(1).unwrap().unwrap(),
+                "2001: A Space Odyssey"
+            );
+        } else {
+            panic!("SELECT statement returned no result set")
+        };
+    }
+
+    assert_eq!(pool.open_connections(), 1);
+    assert_eq!(pool.idle_connections(), 1);
+}
+
+#[test]
+fn checked_out_connection_is_reused() {
+    let env = create_environment_v3().unwrap();
+    let pool = Pool::new(env, CONNECTION_STRING);
+
+    // Temporary tables are only visible to the connection which created them
+    let conn = pool.get().unwrap();
+    Statement::with_parent(&conn)
+        .unwrap()
+        .exec_direct("CREATE TEMPORARY TABLE POOL_REUSE (A INTEGER)")
+        .unwrap();
+    drop(conn);
+
+    let conn = pool.get().unwrap();
+    Statement::with_parent(&conn)
+        .unwrap()
+        .exec_direct("SELECT A FROM POOL_REUSE")
+        .unwrap();
+}
+
+#[test]
+fn pooled_transaction_is_rolled_back() {
+    let env = create_environment_v3().unwrap();
+    let config = PoolConfig {
+        max_size: 1,
+        ..PoolConfig::default()
+    };
+    let pool = Pool::with_config(env, CONNECTION_STRING, config);
+    let count = |conn: &Connection<safe::AutocommitOn>| {
+        let stmt = Statement::with_parent(conn).unwrap();
+        if let Data(mut stmt) = stmt.exec_direct("SELECT COUNT(*) FROM POOL_TRANSACTION").unwrap() {
+            let mut cursor = stmt.fetch().unwrap().unwrap();
+            cursor.get_data::<i32>(1).unwrap().unwrap()
+        } else {
+            panic!("SELECT statement returned no result set")
+        }
+    };
+
+    let conn = pool.get().unwrap();
+    Statement::with_parent(&conn)
+        .unwrap()
+        .exec_direct("CREATE TEMPORARY TABLE POOL_TRANSACTION (A INTEGER)")
+        .unwrap();
+    let mut transaction = conn.transaction().unwrap();
+    Statement::with_parent(&transaction)
+        .unwrap()
+        .exec_direct("INSERT INTO POOL_TRANSACTION (A) VALUES (1)")
+        .unwrap();
+    transaction.commit().unwrap();
+    Statement::with_parent(&transaction)
+        .unwrap()
+        .exec_direct("INSERT INTO POOL_TRANSACTION (A) VALUES (2)")
+        .unwrap();
+    drop(transaction);
+
+    assert_eq!(pool.idle_connections(), 1);
+    let conn = pool.get().unwrap();
+    assert_eq!(count(&conn), 1);
+}
+
+#[test]
+fn pool_exhausted() {
+    let env = create_environment_v3().unwrap();
+    let config = PoolConfig {
+        max_size: 1,
+        connection_timeout: Duration::from_millis(10),
+        ..PoolConfig::default()
+    };
+    let pool = Pool::with_config(env, CONNECTION_STRING, config);
+
+    let first = pool.get().unwrap();
+    let error = pool.get().err().unwrap();
+    assert_eq!(&error.get_raw_state()[..5], b"HYT00");
+    drop(first);
+    pool.get().unwrap();
+}
+
+#[test]
+fn expired_connections_are_closed() {
+    let env = create_environment_v3().unwrap();
+    let config = PoolConfig {
+        idle_timeout: Some(Duration::from_millis(0)),
+        ..PoolConfig::default()
+    };
+    let pool = Pool::with_config(env, CONNECTION_STRING, config);
+
+    drop(pool.get().unwrap());
+    assert_eq!(pool.idle_connections(), 1);
+    drop(pool.get().unwrap());
+    assert_eq!(pool.open_connections(), 1);
+}
+
+#[test]
+fn invalid_pool_connection_string() {
+    let env = create_environment_v3().unwrap();
+    let pool = Pool::new(env, "bla");
+    assert!(pool.get().is_err());
+    assert_eq!(pool.open_connections(), 0);
+}
+
+#[cfg(feature = "r2d2")]
+#[test]
+fn r2d2_pool() {
+    let env = create_environment_v3().unwrap();
+    let manager = OdbcConnectionManager::new(env, CONNECTION_STRING);
+    let pool = r2d2::Pool::builder().max_size(2).build(manager).unwrap();
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let pool = pool.clone();
+            std::thread::spawn(move || {
+                let conn = pool.get().unwrap();
+                let stmt = Statement::with_parent(&conn).unwrap();
+                if let Data(mut stmt) = stmt.exec_direct("SELECT YEAR FROM MOVIES ORDER BY YEAR").unwrap() {
+                    let mut cursor = stmt.fetch().unwrap().unwrap();
+                    assert_eq!(cursor.get_data::<i32>(1).unwrap().unwrap(), 1968);
+                } else {
+                    panic!("SELECT statement returned no result set")
+                };
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
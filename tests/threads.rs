@@ -0,0 +1,108 @@
+//! Contains tests using environments, connections and statements from multiple threads
+extern crate odbc;
+extern crate odbc_safe;
+
+use odbc::*;
+use odbc_safe::AutocommitOn;
+use std::sync::Arc;
+use std::thread;
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+#[test]
+fn send_and_sync() {
+    assert_send::<Environment<Version3>>();
+    assert_sync::<Environment<Version3>>();
+    assert_send::<Connection<AutocommitOn>>();
+    assert_sync::<Connection<AutocommitOn>>();
+    assert_send::<Statement<Allocated, NoResult, AutocommitOn>>();
+    assert_send::<Statement<Prepared, HasResult, AutocommitOn>>();
+    assert_send::<Pool>();
+    assert_sync::<Pool>();
+    assert_send::<DiagnosticRecord>();
+    assert_send::<Error>();
+    assert_sync::<Error>();
+}
+
+fn first_title(conn: &Connection<AutocommitOn>) -> String {
+    let stmt = Statement::with_parent(conn).unwrap();
+    if let Data(mut stmt) = stmt.exec_direct("SELECT TITLE FROM MOVIES ORDER BY YEAR").unwrap() {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        cursor.get_data::<String>(1).unwrap().unwrap()
+    } else {
+        panic!("SELECT statement returned no result set")
+    }
+}
+
+#[test]
+fn move_connection_to_thread() {
+    let env = Arc::new(create_environment_v3().unwrap());
+    let conn = Environment::connect_shared(&env, "TestDataSource", "", "").unwrap();
+    let title = thread::spawn(move || first_title(&conn)).join().unwrap();
+    assert_eq!(title, "2001: A Space Odyssey");
+}
+
+#[test]
+fn connect_from_multiple_threads() {
+    let env = Arc::new(create_environment_v3().unwrap());
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let env = env.clone();
+            thread::spawn(move || {
+                let conn = env.connect("TestDataSource", "", "").unwrap();
+                first_title(&conn)
+            })
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), "2001: A Space Odyssey");
+    }
+}
+
+#[test]
+fn share_connection_between_threads() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..4)
+            .map(|_| scope.spawn(|| first_title(&conn)))
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), "2001: A Space Odyssey");
+        }
+    });
+}
+
+#[test]
+fn move_statement_with_result_set_to_thread() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let year = 1993;
+    let stmt = stmt.bind_parameter(1, &year).unwrap();
+    let result = stmt.exec_direct("SELECT TITLE FROM MOVIES WHERE YEAR = ?").unwrap();
+    thread::scope(|scope| {
+        scope.spawn(move || if let Data(mut stmt) = result {
+            let mut cursor = stmt.fetch().unwrap().unwrap();
+            assert_eq!(cursor.get_data::<String>(1).unwrap().unwrap(), "Jurassic Park");
+        } else {
+            panic!("SELECT statement returned no result set")
+        });
+    });
+}
+
+#[test]
+fn share_pool_between_threads() {
+    let env = create_environment_v3().unwrap();
+    let pool = Pool::new(env, "dsn=TestDataSource;Uid=;Pwd=;");
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                let conn = pool.get().unwrap();
+                assert_eq!(first_title(&conn), "2001: A Space Odyssey");
+            });
+        }
+    });
+    assert!(pool.open_connections() <= 4);
+}
@@ -0,0 +1,36 @@
+//! Tests for timestamps with time zone offset
+extern crate odbc;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+
+use odbc::*;
+
+#[test]
+fn timestamp_offset_from_text() {
+    let env = create_environment_v3().unwrap();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let sql = "SELECT '2021-03-14 15:09:26.5+01:00', 'not a timestamp', NULL";
+    if let Data(mut stmt) = stmt.exec_direct(sql).unwrap() {
+        let mut cursor = stmt.fetch().unwrap().unwrap();
+        let ts = cursor.get_timestamp_offset(1).unwrap().unwrap();
+        assert_eq!(ts.to_string(), "2021-03-14 15:09:26.5 +01:00");
+        let error = cursor.get_timestamp_offset(2).unwrap_err();
+        assert_eq!(error.get_raw_state(), b"22007\0");
+        assert_eq!(cursor.get_timestamp_offset(3).unwrap(), None);
+    } else {
+        panic!("SELECT statement returned no result set")
+    };
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn timestamp_offset_chrono() {
+    use chrono::{DateTime, FixedOffset};
+    use std::convert::TryFrom;
+
+    let ts: SqlTimestampOffset = "2021-03-14 15:09:26.5-05:30".parse().unwrap();
+    let datetime = DateTime::<FixedOffset>::try_from(ts).unwrap();
+    assert_eq!(datetime.to_rfc3339(), "2021-03-14T15:09:26.500-05:30");
+    assert_eq!(SqlTimestampOffset::try_from(datetime), Ok(ts));
+}
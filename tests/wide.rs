@@ -0,0 +1,55 @@
+//! Tests for calling the wide (UTF-16) variants of the ODBC functions
+extern crate odbc;
+
+use odbc::*;
+
+fn wide_environment() -> Environment<Version3> {
+    let mut env = create_environment_v3().unwrap();
+    env.set_encodings(Encodings {
+        wide: true,
+        ..Encodings::default()
+    });
+    env
+}
+
+#[test]
+fn wide_column_names() {
+    let env = wide_environment();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    match stmt.exec_direct("SELECT 'Grüße, 你好' AS \"Größe\"").unwrap() {
+        Data(mut stmt) => {
+            assert_eq!(stmt.describe_col(1).unwrap().name, "Größe");
+            let mut cursor = stmt.fetch().unwrap().unwrap();
+            assert_eq!(cursor.get_data::<String>(1).unwrap().unwrap(), "Grüße, 你好");
+        }
+        NoData(_) => panic!("SELECT did not return result set"),
+    };
+}
+
+#[test]
+fn wide_prepare() {
+    let env = wide_environment();
+    let conn = env.connect_with_connection_string("dsn=TestDataSource;Uid=;Pwd=;").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let stmt = stmt.prepare("SELECT TITLE AS \"Titel ✓\" FROM MOVIES WHERE YEAR = ?").unwrap();
+    assert_eq!(stmt.describe_col(1).unwrap().name, "Titel ✓");
+    let stmt = stmt.bind_parameter(1, &1993).unwrap();
+    match stmt.execute().unwrap() {
+        Data(mut stmt) => {
+            let mut cursor = stmt.fetch().unwrap().unwrap();
+            assert_eq!(cursor.get_data::<String>(1).unwrap().unwrap(), "Jurassic Park");
+        }
+        NoData(_) => panic!("SELECT did not return result set"),
+    };
+}
+
+#[test]
+fn wide_diagnostics() {
+    let env = wide_environment();
+    let conn = env.connect("TestDataSource", "", "").unwrap();
+    let stmt = Statement::with_parent(&conn).unwrap();
+    let error = stmt.exec_direct("SELECT * FROM NICHT_VORHANDEN_ÄÖÜ").err().unwrap();
+    assert!(error.to_string().contains("NICHT_VORHANDEN_ÄÖÜ"));
+    assert!(env.connect("DoesNotExist", "", "").is_err());
+}